@@ -18,7 +18,7 @@ struct AppState {
 #[debug_handler]
 async fn home(State(state): State<Arc<AppState>>) -> Html<String> {
     let mut template = String::new();
-    let _ = File::open(resolve_path(file!(), "../index.html"))
+    let _ = File::open(resolve_path(file!(), "../index.html").unwrap())
         .unwrap()
         .read_to_string(&mut template);
 
@@ -33,11 +33,17 @@ async fn home(State(state): State<Arc<AppState>>) -> Html<String> {
 async fn main() -> std::io::Result<()> {
     env_logger::init();
 
-    let manifest_path: String = resolve_path(file!(), "../dist/.vite/manifest.json");
-    let mut vite_config: ViteConfig<'_> = ViteConfig::default().set_manifest_path(&manifest_path);
-    vite_config.entrypoints = Some(vec!["src/www/main.tsx", "src/www/index.css"]);
+    let manifest_path: String =
+        resolve_path(file!(), "../dist/.vite/manifest.json").map_err(std::io::Error::other)?;
+    // `VITE_RUST_*` environment variables may override the defaults, e.g. `VITE_RUST_SERVER_HOST`
+    let vite_config = ViteConfig::from_env()
+        .map_err(std::io::Error::other)?
+        .set_manifest_path(manifest_path)
+        .set_entrypoints(["src/www/main.tsx", "src/www/index.css"]);
 
-    let vite = vite_rust::Vite::new(vite_config).await.unwrap();
+    let vite = vite_rust::Vite::new(vite_config)
+        .await
+        .map_err(std::io::Error::other)?;
 
     let state: Arc<AppState> = Arc::new(AppState { vite });
 
@@ -1,8 +1,11 @@
+use crate::tag::{Tag, TagKind};
+
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) enum Asset {
     StyleSheet(String),
     EntryPoint(String),
     Preload(String),
+    Prefetch(String),
 }
 
 enum PreloadAsset {
@@ -28,35 +31,105 @@ impl Asset {
         Self::Preload(Self::resolve_asset_path(file, prefix, app_url))
     }
 
-    pub fn into_html(self) -> String {
-        match self {
-            Self::StyleSheet(file) => {
-                format!(r#"<link rel="stylesheet" href="{file}" />"#)
+    pub fn pre_fetch(file: String, prefix: Option<&str>, app_url: &str) -> Self {
+        Self::Prefetch(Self::resolve_asset_path(file, prefix, app_url))
+    }
+
+    /// Converts the asset into a [`Tag`] with optional security attributes:
+    /// - `integrity` adds `integrity` and `crossorigin` attributes to stylesheets,
+    ///   module scripts and modulepreloads;
+    /// - `nonce` adds a CSP `nonce` attribute to every tag.
+    ///
+    /// Returns `None` for preloads of unknown file types.
+    pub fn into_tag(self, integrity: Option<&str>, nonce: Option<&str>) -> Option<Tag> {
+        let tag = match self {
+            Self::StyleSheet(file) => Tag::new(TagKind::StyleSheet)
+                .with_attribute("rel", "stylesheet")
+                .with_attribute("href", file),
+            Self::EntryPoint(file) => Tag::new(TagKind::ModuleScript)
+                .with_attribute("type", "module")
+                .with_attribute("src", file),
+            Self::Preload(file) => {
+                let preload_as = match Asset::get_file_type(&file) {
+                    PreloadAsset::JavaScript => {
+                        let tag = Tag::new(TagKind::ModulePreload)
+                            .with_attribute("rel", "modulepreload")
+                            .with_attribute("href", file);
+
+                        return Some(with_security_attributes(tag, integrity, nonce));
+                    }
+                    PreloadAsset::Css => "style",
+                    PreloadAsset::Audio => "audio",
+                    PreloadAsset::Font => "font",
+                    PreloadAsset::Image => "image",
+                    PreloadAsset::Video => "video",
+                    PreloadAsset::Unknown => return None,
+                };
+
+                let mut tag = Tag::new(TagKind::Preload)
+                    .with_attribute("rel", "preload")
+                    .with_attribute("as", preload_as)
+                    .with_attribute("href", file);
+
+                // fonts are always fetched in CORS mode
+                if preload_as == "font" {
+                    tag.set_boolean_attribute("crossorigin");
+                }
+
+                return Some(with_security_attributes(tag, None, nonce));
             }
-            Self::EntryPoint(file) => {
-                format!(r#"<script type="module" src="{file}"></script>"#)
+            Self::Prefetch(file) => {
+                let tag = Tag::new(TagKind::Prefetch)
+                    .with_attribute("rel", "prefetch")
+                    .with_attribute("href", file);
+
+                return Some(with_security_attributes(tag, None, nonce));
             }
+        };
+
+        Some(with_security_attributes(tag, integrity, nonce))
+    }
+
+    /// Converts the asset into a [RFC 8288] `Link` header value, suitable for
+    /// `Link` headers and 103 Early Hints responses. Its `as` and `crossorigin`
+    /// values match the ones of [`Asset::into_tag`], so that browsers reuse the
+    /// preloaded response; `crossorigin` tells whether the tag carries an integrity
+    /// hash, and so a `crossorigin` attribute.
+    ///
+    /// Returns `None` for the assets that can't be preloaded, and for prefetches,
+    /// which aren't needed by the page being loaded.
+    ///
+    /// [RFC 8288]: https://www.rfc-editor.org/rfc/rfc8288
+    pub fn into_link_header(self, crossorigin: bool) -> Option<String> {
+        let header = match self {
+            Self::StyleSheet(file) => format!("<{file}>; rel=preload; as=style"),
+            Self::EntryPoint(file) => format!("<{file}>; rel=modulepreload"),
             Self::Preload(file) => match Asset::get_file_type(&file) {
-                PreloadAsset::JavaScript => {
-                    format!(r#"<link rel="modulepreload" href="{file}" />"#)
-                }
-                PreloadAsset::Css => {
-                    format!(r#"<link rel="preload" as="style" href="{file}" />"#)
-                }
-                PreloadAsset::Audio => {
-                    format!(r#"<link rel="preload" as="font" href="{file}" />"#)
-                }
+                PreloadAsset::JavaScript => format!("<{file}>; rel=modulepreload"),
+                PreloadAsset::Css => format!("<{file}>; rel=preload; as=style"),
+                PreloadAsset::Audio => format!("<{file}>; rel=preload; as=audio"),
                 PreloadAsset::Font => {
-                    format!(r#"<link rel="preload" as="audio" href="{file}" />"#)
+                    return Some(format!("<{file}>; rel=preload; as=font; crossorigin"))
                 }
-                PreloadAsset::Image => {
-                    format!(r#"<link rel="preload" as="image" href="{file}" />"#)
-                }
-                PreloadAsset::Video => {
-                    format!(r#"<link rel="preload" as="video" href="{file}" />"#)
-                }
-                PreloadAsset::Unknown => "".into(),
+                PreloadAsset::Image => format!("<{file}>; rel=preload; as=image"),
+                PreloadAsset::Video => format!("<{file}>; rel=preload; as=video"),
+                PreloadAsset::Unknown => return None,
             },
+            Self::Prefetch(_) => return None,
+        };
+
+        match crossorigin {
+            true => Some(format!("{header}; crossorigin")),
+            false => Some(header),
+        }
+    }
+
+    /// Whether an integrity hash would be rendered for this asset.
+    pub fn supports_integrity(&self) -> bool {
+        match self {
+            Self::StyleSheet(_) | Self::EntryPoint(_) => true,
+            Self::Preload(file) => matches!(Asset::get_file_type(file), PreloadAsset::JavaScript),
+            Self::Prefetch(_) => false,
         }
     }
 
@@ -108,12 +181,140 @@ impl Asset {
     }
 }
 
-impl std::hash::Hash for Asset {
-    fn hash<H: std::hash::Hasher>(&self, hasher: &mut H) {
-        match self {
-            Asset::EntryPoint(v) => v.hash(hasher),
-            Asset::Preload(v) => v.hash(hasher),
-            Asset::StyleSheet(v) => v.hash(hasher),
+/// Whether the given entrypoint or file is a stylesheet, judging by its extension
+/// (case-insensitive, with or without the leading dot). Query strings and fragments,
+/// such as `?inline`, are ignored.
+pub(crate) fn is_style_sheet<S: AsRef<str>>(path: &str, extensions: &[S]) -> bool {
+    let path = path.split(['?', '#']).next().unwrap_or(path);
+    let Some((_, extension)) = path.rsplit_once('.') else {
+        return false;
+    };
+
+    extensions.iter().any(|candidate| {
+        candidate
+            .as_ref()
+            .trim_start_matches('.')
+            .eq_ignore_ascii_case(extension)
+    })
+}
+
+/// Inline script preventing Safari 10.1 from running `nomodule` scripts, since it
+/// supports ES modules but ignores the `nomodule` attribute.
+/// Same as the one injected by `@vitejs/plugin-legacy`.
+const SAFARI_10_NOMODULE_FIX: &str = r#"!function(){var e=document,t=e.createElement("script");if(!("noModule"in t)&&"onbeforeload"in t){var n=!1;e.addEventListener("beforeload",(function(e){if(e.target===t)n=!0;else if(!e.target.hasAttribute("nomodule")||!n)return;e.preventDefault()}),!0),t.type="module",t.src=".",e.head.appendChild(t),t.remove()}}();"#;
+
+/// Builds the `nomodule` scripts for browsers without ES modules support, as
+/// `@vitejs/plugin-legacy` does: the Safari 10.1 fix, the legacy polyfills and
+/// every legacy entry, loaded through SystemJS from their `data-src` attribute.
+pub(crate) fn legacy_tags(
+    polyfills: String,
+    entries: Vec<String>,
+    nonce: Option<&str>,
+) -> Vec<Tag> {
+    let safari_fix = Tag::new(TagKind::InlineScript)
+        .with_boolean_attribute("nomodule")
+        .with_content(SAFARI_10_NOMODULE_FIX);
+
+    let polyfills = Tag::new(TagKind::LegacyPolyfills)
+        .with_boolean_attribute("nomodule")
+        .with_boolean_attribute("crossorigin")
+        .with_attribute("id", "vite-legacy-polyfill")
+        .with_attribute("src", polyfills);
+
+    let mut tags = vec![
+        with_security_attributes(safari_fix, None, nonce),
+        with_security_attributes(polyfills, None, nonce),
+    ];
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let id = match index {
+            0 => "vite-legacy-entry".to_string(),
+            index => format!("vite-legacy-entry-{index}"),
+        };
+
+        let entry = Tag::new(TagKind::LegacyEntry)
+            .with_boolean_attribute("nomodule")
+            .with_boolean_attribute("crossorigin")
+            .with_attribute("id", &id)
+            .with_attribute("data-src", entry)
+            .with_content(format!(
+                "System.import(document.getElementById('{id}').getAttribute('data-src'))"
+            ));
+
+        tags.push(with_security_attributes(entry, None, nonce));
+    }
+
+    tags
+}
+
+/// Appends the `integrity`, `crossorigin` and `nonce` attributes to the tag, if given.
+pub(crate) fn with_security_attributes(
+    mut tag: Tag,
+    integrity: Option<&str>,
+    nonce: Option<&str>,
+) -> Tag {
+    if let Some(integrity) = integrity {
+        tag.set_attribute("integrity", integrity)
+            .set_attribute("crossorigin", "anonymous");
+    }
+
+    if let Some(nonce) = nonce {
+        tag.set_attribute("nonce", nonce);
+    }
+
+    tag
+}
+
+#[cfg(test)]
+mod test {
+    use super::Asset;
+
+    #[test]
+    fn test_link_headers_match_tags() {
+        let files = [
+            "/assets/app.js",
+            "/assets/app.css",
+            "/assets/inter.woff2",
+            "/assets/click.mp3",
+            "/assets/logo.svg",
+            "/assets/intro.mp4",
+        ];
+
+        for file in files {
+            let tag = Asset::Preload(file.to_string())
+                .into_tag(None, None)
+                .unwrap();
+            let header = Asset::Preload(file.to_string())
+                .into_link_header(false)
+                .unwrap();
+
+            let expected = match tag.get_attribute("rel").unwrap() {
+                "modulepreload" => format!("<{file}>; rel=modulepreload"),
+                rel => format!(
+                    "<{file}>; rel={rel}; as={}",
+                    tag.get_attribute("as").unwrap()
+                ),
+            };
+            let expected = match tag.has_attribute("crossorigin") {
+                true => format!("{expected}; crossorigin"),
+                false => expected,
+            };
+
+            assert_eq!(expected, header);
         }
+
+        let tag = Asset::StyleSheet("/assets/app.css".into())
+            .into_tag(Some("sha384-hash"), None)
+            .unwrap();
+        assert!(tag.has_attribute("crossorigin"));
+        assert_eq!(
+            Some("</assets/app.css>; rel=preload; as=style; crossorigin".to_string()),
+            Asset::StyleSheet("/assets/app.css".into()).into_link_header(true)
+        );
+
+        assert_eq!(
+            None,
+            Asset::Prefetch("/assets/lazy.js".into()).into_link_header(false)
+        );
     }
 }
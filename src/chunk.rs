@@ -77,24 +77,28 @@ impl ChunkIterListTrack {
     }
 }
 
-struct ChunkIter<'a> {
+struct ChunkIter<'a, 'b> {
     assets: &'a [String],
     imports: &'a [String],
     index: usize,
     css: &'a [String],
     track: ChunkIterListTrack,
-    prefix: Option<&'a str>,
-    app_url: &'a str,
+    prefix: Option<&'b str>,
+    app_url: &'b str,
 }
 
 impl Chunk {
-    /// Returns an [`Iterator<Item = Asset>`], where the returned assets
-    /// are the Chunk's `assets`, `imports` and `css` fields, respectively.
-    pub fn assets_iter(
-        &self,
-        prefix: Option<&'static str>,
-        app_url: &'static str,
-    ) -> impl Iterator<Item = Asset> + '_ {
+    /// Returns an [`Iterator<Item = (Asset, &str)>`], where the returned assets
+    /// are the Chunk's `assets`, `imports` and `css` fields, respectively,
+    /// paired with the bundled file they were resolved from.
+    pub fn assets_iter<'a, 'b>(
+        &'a self,
+        prefix: Option<&'b str>,
+        app_url: &'b str,
+    ) -> impl Iterator<Item = (Asset, &'a str)> + 'b
+    where
+        'a: 'b,
+    {
         ChunkIter {
             assets: &self.assets,
             imports: &self.imports,
@@ -107,14 +111,15 @@ impl Chunk {
     }
 }
 
-impl Iterator for ChunkIter<'_> {
-    type Item = Asset;
+impl<'a> Iterator for ChunkIter<'a, '_> {
+    type Item = (Asset, &'a str);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.track == ChunkIterListTrack::Assets {
             if let Some(asset) = self.assets.get(self.index) {
                 self.index += 1;
-                return Some(Asset::pre_load(asset.clone(), self.prefix, self.app_url));
+                let preload = Asset::pre_load(asset.clone(), self.prefix, self.app_url);
+                return Some((preload, asset));
             } else {
                 self.track = ChunkIterListTrack::Css;
                 self.index = 0;
@@ -124,7 +129,8 @@ impl Iterator for ChunkIter<'_> {
         if self.track == ChunkIterListTrack::Css {
             if let Some(css) = self.css.get(self.index) {
                 self.index += 1;
-                return Some(Asset::style_sheet(css.clone(), self.prefix, self.app_url));
+                let style_sheet = Asset::style_sheet(css.clone(), self.prefix, self.app_url);
+                return Some((style_sheet, css));
             } else {
                 self.track = ChunkIterListTrack::Eot;
                 self.index = 0;
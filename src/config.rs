@@ -1,62 +1,133 @@
+use serde::Deserialize;
 use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
+use crate::error::{ViteError, ViteErrorKind};
+#[cfg(feature = "async")]
 use crate::utils::check_heart_beat;
+use crate::utils::{check_heart_beat_blocking, HeartBeatOptions};
+use crate::utils::{host_name, is_local_host, split_url, DevServerTls};
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ViteMode {
     Development,
     Manifest,
 }
 
 impl ViteMode {
+    #[cfg(feature = "async")]
     pub(crate) async fn discover(
         use_hb: bool,
         use_dev_server: bool,
+        is_production: bool,
         host: &str,
-        hb_retries: u8,
-    ) -> ViteMode {
-        if !use_hb {
-            return ViteMode::Development;
+        hb_options: &HeartBeatOptions,
+    ) -> Result<ViteMode, ViteError> {
+        if let Some(mode) = ViteMode::discover_without_probe(use_hb, use_dev_server, is_production)
+        {
+            return Ok(mode);
         }
 
-        if !use_dev_server {
-            return ViteMode::Manifest;
-        }
+        let dev_server_is_ok = check_heart_beat(host, hb_options).await?;
 
-        if is_production() {
-            return ViteMode::Manifest;
+        Ok(match dev_server_is_ok {
+            true => ViteMode::Development,
+            false => ViteMode::Manifest,
+        })
+    }
+
+    /// Does the same as [`ViteMode::discover`], probing the dev-server with a
+    /// blocking request.
+    pub(crate) fn discover_blocking(
+        use_hb: bool,
+        use_dev_server: bool,
+        is_production: bool,
+        host: &str,
+        hb_options: &HeartBeatOptions,
+    ) -> ViteMode {
+        if let Some(mode) = ViteMode::discover_without_probe(use_hb, use_dev_server, is_production)
+        {
+            return mode;
         }
 
-        let dev_server_is_ok = check_heart_beat(host, None, hb_retries).await;
+        let dev_server_is_ok = check_heart_beat_blocking(host, hb_options);
 
         match dev_server_is_ok {
             true => ViteMode::Development,
             false => ViteMode::Manifest,
         }
     }
-}
 
-fn is_production() -> bool {
-    env::vars().any(|(k, v)| {
-        if [
-            "RUST_ENV",
-            "NODE_ENV",
-            "APP_ENV",
-            "__TEST_APP_ENV",
-            "LOCO_ENV",
-            "RAILS_ENV",
-        ]
-        .contains(&k.as_str())
-        {
-            return v == "production";
+    /// Picks the mode without reaching the dev-server, if the config allows it.
+    fn discover_without_probe(
+        use_hb: bool,
+        use_dev_server: bool,
+        is_production: bool,
+    ) -> Option<ViteMode> {
+        if !use_hb {
+            return Some(ViteMode::Development);
+        }
+
+        if !use_dev_server || is_production {
+            return Some(ViteMode::Manifest);
         }
 
-        false
-    })
+        None
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct ViteConfig<'a> {
+/// The connect and read timeout of each heart beat attempt by default.
+pub const DEFAULT_HEART_BEAT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long the heart beat check may take overall by default.
+pub const DEFAULT_HEART_BEAT_DEADLINE: Duration = Duration::from_secs(5);
+
+/// The extensions of the entrypoints considered stylesheets by default.
+pub const DEFAULT_STYLE_SHEET_EXTENSIONS: [&str; 8] = [
+    ".css", ".scss", ".sass", ".less", ".styl", ".stylus", ".pcss", ".postcss",
+];
+
+/// The environment variables looked for by default to detect a production environment.
+pub const DEFAULT_PRODUCTION_ENV_VARS: [&str; 5] =
+    ["RUST_ENV", "NODE_ENV", "APP_ENV", "LOCO_ENV", "RAILS_ENV"];
+
+/// The values of [`ViteConfig::production_env_vars`] that mean a production
+/// environment by default.
+pub const DEFAULT_PRODUCTION_ENV_VALUES: [&str; 1] = ["production"];
+
+/// A custom function deciding whether the application runs in a production-like
+/// environment, set with [`ViteConfig::set_production_predicate`].
+#[derive(Clone, Copy)]
+pub struct ProductionPredicate(pub fn() -> bool);
+
+impl fmt::Debug for ProductionPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ProductionPredicate")
+    }
+}
+
+impl PartialEq for ProductionPredicate {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::fn_addr_eq(self.0, other.0)
+    }
+}
+
+impl Eq for ProductionPredicate {}
+
+/// Vite's configuration.
+///
+/// Besides the fluent setters, it can be deserialized with `serde` (missing fields
+/// take their default values), or loaded from `VITE_RUST_*` environment variables
+/// and config files with [`ViteConfig::from_env`], [`ViteConfig::from_file`] and
+/// [`ViteConfig::load`].
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+#[serde(default)]
+pub struct ViteConfig {
     /// The `path/to/manifest.json` file.
     /// Currently, Vite won't resolve relative paths, so please consider
     /// your current working directory as the root of it and start the path
@@ -64,8 +135,8 @@ pub struct ViteConfig<'a> {
     ///
     /// **Optionally and experimentally**, you can use the [`resolve_path`]
     /// method for the manifest file resolution. However, this method might
-    /// come to fail at some point, and will also panic in the many situations
-    /// described on its documentation.
+    /// come to fail at some point, and will also return errors in the many
+    /// situations described on its documentation.
     ///
     /// # Example
     /// ```plaintext
@@ -82,22 +153,22 @@ pub struct ViteConfig<'a> {
     /// use vite_rust::{ViteConfig, utils::resolve_path};
     ///
     /// let config = ViteConfig {
-    ///     manifest_path: Some("public/dist/manifest.json"),
+    ///     manifest_path: Some("public/dist/manifest.json".to_string()),
     ///     // or
-    ///     manifest_path: Some(resolve_path(file!(), "../public/dist/manifest.json")),
+    ///     manifest_path: Some(resolve_path(file!(), "../public/dist/manifest.json")?),
     ///     // ...
     /// };
     /// ```
-    pub manifest_path: Option<&'a str>,
+    pub manifest_path: Option<String>,
     /// Defines which entrypoints Vite will use to generate the html `script`,
     /// `link` and `stylesheet` tags.
     ///
     /// If `None` is provided, Vite will scan the manifest for files with
     /// `isEntry: true` property and consider them the entrypoints.
-    pub entrypoints: Option<Vec<&'a str>>,
+    pub entrypoints: Option<Vec<String>>,
     /// If `None` is provided, Vite will discover which one to use considering:
-    /// -   any of `RUST_ENV`, `NODE_ENV` or `APP_ENV` environment variables exists
-    ///     and is set to `true`;
+    /// -   whether the application runs in a production-like environment (see
+    ///     [`ViteConfig::is_production`]);
     /// -   Dev-server is running;
     /// -   Heart beat check is enabled.
     ///
@@ -109,6 +180,26 @@ pub struct ViteConfig<'a> {
     pub use_heart_beat_check: bool,
     /// How many times heartbeat checker should try before fallbacking.
     pub heart_beat_retries_limit: Option<u8>,
+    /// Connect and read timeout of each heart beat attempt. Defaults to
+    /// [`DEFAULT_HEART_BEAT_TIMEOUT`], which is also used if `None`.
+    ///
+    /// In config files and environment variables, durations are given in milliseconds.
+    #[serde(deserialize_with = "deserialize_millis")]
+    pub heart_beat_timeout: Option<Duration>,
+    /// Delay before the first heart beat retry, multiplied by `heart_beat_backoff_factor`
+    /// after each retry. If `None`, retries happen right away.
+    #[serde(deserialize_with = "deserialize_millis")]
+    pub heart_beat_initial_delay: Option<Duration>,
+    /// By how much the delay between heart beat retries grows after each retry.
+    pub heart_beat_backoff_factor: u32,
+    /// Whether each delay between heart beat retries is randomly shortened by up
+    /// to a half.
+    pub heart_beat_jitter: bool,
+    /// How long the heart beat check may take overall, retries included. Once it's
+    /// over, the dev-server is considered down. Defaults to [`DEFAULT_HEART_BEAT_DEADLINE`],
+    /// and only the retries limit bounds the check if `None`.
+    #[serde(deserialize_with = "deserialize_millis")]
+    pub heart_beat_deadline: Option<Duration>,
     /// Whether dev server should be considered or not.
     ///
     /// If false, `force_mode` should be either `Manifest` or `None`,
@@ -118,28 +209,448 @@ pub struct ViteConfig<'a> {
     /// Normally, it would be `"http://localhost:5173"`.
     ///
     /// Please, do not forget the protocol (http, https)!
-    pub server_host: Option<&'a str>,
+    pub server_host: Option<String>,
     /// Prefix assets path with the given `str`.
-    pub prefix: Option<&'a str>,
+    pub prefix: Option<String>,
     /// Add a custom domain to prefix every asset URL with.
-    pub app_url: Option<&'a str>,
+    pub app_url: Option<String>,
+    /// Whether stylesheet, module script and modulepreload tags generated from
+    /// the manifest should carry `integrity` and `crossorigin` attributes.
+    ///
+    /// The hash is taken from the chunk's `integrity` field when present.
+    /// Otherwise, a `sha384` hash is computed from the bundled file, which is
+    /// looked up relatively to the manifest's output directory.
+    pub subresource_integrity: bool,
+    /// How many levels of dynamic imports (`import()`) to follow from the
+    /// entrypoints when generating `<link rel="prefetch">` hints for their
+    /// scripts and stylesheets. E.g., `1` prefetches the chunks directly lazy-loaded
+    /// by the entrypoints, such as split routes.
+    ///
+    /// Files that are already eagerly loaded are never prefetched. If `None`
+    /// is provided, no prefetch hint is generated.
+    pub prefetch_depth: Option<u8>,
+    /// The extensions of the entrypoints that are stylesheets, and hence rendered as
+    /// `<link rel="stylesheet">` tags instead of module scripts. Entries whose bundled
+    /// file is a `.css` file are stylesheets as well.
+    ///
+    /// Defaults to [`DEFAULT_STYLE_SHEET_EXTENSIONS`].
+    pub style_sheet_extensions: Vec<String>,
+    /// Path to a "hot" file written by the Vite plugin while the dev-server is
+    /// running (e.g. `public/hot`), holding the dev-server's real URL.
+    ///
+    /// When set and `force_mode` is `None`, the file replaces the heart beat check:
+    /// its presence means `Development` mode, using its URL as `server_host`, and
+    /// its absence means `Manifest` mode.
+    pub hot_file_path: Option<String>,
+    /// Whether the backend forwards the dev-server requests to Vite, so that the URLs
+    /// generated in `Development` mode are same-origin (relative to `app_url`) instead
+    /// of pointing at `server_host`.
+    ///
+    /// Set it when serving them through the `DevServerProxy` (`proxy` feature) or
+    /// any other reverse proxy.
+    pub proxy_dev_server: bool,
+    /// Whether the HMR client script is followed by a snippet showing the reloads and
+    /// errors sent by the backend through Vite's HMR channel, e.g. with
+    /// `Vite::show_error_overlay` (`hmr` feature). Vite relays them through
+    /// `vite-rs-plugin`.
+    pub backend_hmr_events: bool,
+    /// Path to a PEM-encoded CA certificate trusted, along with the system's ones, when
+    /// connecting to a `https` dev-server, e.g. mkcert's `rootCA.pem`.
+    pub dev_server_ca_path: Option<String>,
+    /// Path to the PEM-encoded certificate of a `https` dev-server, which is then the
+    /// only one trusted, e.g. a self-signed certificate.
+    pub pinned_dev_server_cert_path: Option<String>,
+    /// Whether invalid certificates, such as the self-signed ones of
+    /// `@vitejs/plugin-basic-ssl`, are accepted from the dev-server. They are only
+    /// ever accepted when it runs on localhost.
+    pub accept_invalid_dev_server_certs: bool,
+    /// The environment variables telling whether the application runs in a
+    /// production-like environment, in which case the dev-server is never looked for.
+    ///
+    /// Defaults to [`DEFAULT_PRODUCTION_ENV_VARS`].
+    pub production_env_vars: Vec<String>,
+    /// The values of `production_env_vars` meaning a production-like environment,
+    /// e.g. `["production", "staging"]`.
+    ///
+    /// Defaults to [`DEFAULT_PRODUCTION_ENV_VALUES`].
+    pub production_env_values: Vec<String>,
+    /// A custom function replacing the `production_env_vars` lookup to decide
+    /// whether the application runs in a production-like environment.
+    #[serde(skip)]
+    pub production_predicate: Option<ProductionPredicate>,
 }
 
-impl<'a> ViteConfig<'a> {
+impl ViteConfig {
     /// Creates a new `ViteConfig` instance with `manifest_path` and `entrypoints` fields set.
-    pub fn new(manifest_path: &'a str, entrypoints: Vec<&'a str>) -> Self {
+    pub fn new<E, S>(manifest_path: impl Into<String>, entrypoints: E) -> Self
+    where
+        E: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
         ViteConfig::default()
             .set_manifest_path(manifest_path)
             .set_entrypoints(entrypoints)
     }
 
-    pub fn set_manifest_path(mut self, manifest_path: &'a str) -> Self {
-        self.manifest_path = Some(manifest_path);
+    /// Creates a `ViteConfig` with default values overridden by the `VITE_RUST_*`
+    /// environment variables that are set.
+    ///
+    /// | Variable                             | Field                      |
+    /// |--------------------------------------|----------------------------|
+    /// | `VITE_RUST_MANIFEST_PATH`            | `manifest_path`            |
+    /// | `VITE_RUST_ENTRYPOINTS`              | `entrypoints` (comma-separated) |
+    /// | `VITE_RUST_FORCE_MODE`               | `force_mode` (`development` or `manifest`) |
+    /// | `VITE_RUST_USE_HEART_BEAT_CHECK`     | `use_heart_beat_check`     |
+    /// | `VITE_RUST_HEART_BEAT_RETRIES_LIMIT` | `heart_beat_retries_limit` |
+    /// | `VITE_RUST_HEART_BEAT_TIMEOUT`       | `heart_beat_timeout` (milliseconds) |
+    /// | `VITE_RUST_HEART_BEAT_INITIAL_DELAY` | `heart_beat_initial_delay` (milliseconds) |
+    /// | `VITE_RUST_HEART_BEAT_BACKOFF_FACTOR`| `heart_beat_backoff_factor` |
+    /// | `VITE_RUST_HEART_BEAT_JITTER`        | `heart_beat_jitter`        |
+    /// | `VITE_RUST_HEART_BEAT_DEADLINE`      | `heart_beat_deadline` (milliseconds) |
+    /// | `VITE_RUST_ENABLE_DEV_SERVER`        | `enable_dev_server`        |
+    /// | `VITE_RUST_SERVER_HOST`              | `server_host`              |
+    /// | `VITE_RUST_PREFIX`                   | `prefix`                   |
+    /// | `VITE_RUST_APP_URL`                  | `app_url`                  |
+    /// | `VITE_RUST_SUBRESOURCE_INTEGRITY`    | `subresource_integrity`    |
+    /// | `VITE_RUST_PREFETCH_DEPTH`           | `prefetch_depth`           |
+    /// | `VITE_RUST_STYLE_SHEET_EXTENSIONS`   | `style_sheet_extensions` (comma-separated) |
+    /// | `VITE_RUST_HOT_FILE_PATH`            | `hot_file_path`            |
+    /// | `VITE_RUST_PROXY_DEV_SERVER`         | `proxy_dev_server`         |
+    /// | `VITE_RUST_BACKEND_HMR_EVENTS`       | `backend_hmr_events`       |
+    /// | `VITE_RUST_DEV_SERVER_CA_PATH`       | `dev_server_ca_path`       |
+    /// | `VITE_RUST_PINNED_DEV_SERVER_CERT_PATH` | `pinned_dev_server_cert_path` |
+    /// | `VITE_RUST_ACCEPT_INVALID_DEV_SERVER_CERTS` | `accept_invalid_dev_server_certs` |
+    /// | `VITE_RUST_PRODUCTION_ENV_VARS`      | `production_env_vars` (comma-separated) |
+    /// | `VITE_RUST_PRODUCTION_ENV_VALUES`    | `production_env_values` (comma-separated) |
+    ///
+    /// # Errors
+    /// Returns an `InvalidConfig` error if a variable holds an invalid value,
+    /// e.g. `VITE_RUST_PREFETCH_DEPTH=many`.
+    pub fn from_env() -> Result<Self, ViteError> {
+        ViteConfig::from_env_with(|name| env::var(name).ok())
+    }
+
+    /// Same as [`ViteConfig::from_env`], but reads the `VITE_RUST_*` variables
+    /// through `lookup` instead of the process environment, e.g. from a map
+    /// loaded out of a `.env` file.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use vite_rust::ViteConfig;
+    ///
+    /// let vars = HashMap::from([("VITE_RUST_PREFIX", "bundle")]);
+    /// let config = ViteConfig::from_env_with(|name| vars.get(name).map(|v| v.to_string()))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(config.prefix.as_deref(), Some("bundle"));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an `InvalidConfig` error if a variable holds an invalid value.
+    pub fn from_env_with(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ViteError> {
+        let mut config = ViteConfig::default();
+        ConfigLayer::from_env(&lookup)?.apply(&mut config);
+        Ok(config)
+    }
+
+    /// Creates a `ViteConfig` with default values overridden by the ones in the
+    /// given file. The file has the same fields as [`ViteConfig`], and its format
+    /// is picked from its extension: `.json`, or `.toml` if the `toml` feature
+    /// is enabled.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if the file can't be read or parsed.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ViteError> {
+        let mut config = ViteConfig::default();
+        ConfigLayer::from_file(path.as_ref())?.apply(&mut config);
+        Ok(config)
+    }
+
+    /// Creates a `ViteConfig` from the `VITE_RUST_*` environment variables (see
+    /// [`ViteConfig::from_env`]) and the given file, if any. File values take
+    /// precedence over environment ones, and setters called on the result take
+    /// precedence over both.
+    ///
+    /// # Example
+    /// ```rust
+    /// use vite_rust::ViteConfig;
+    ///
+    /// // tests/vite-rust.json sets `"prefix": "bundle"`, overriding any
+    /// // `VITE_RUST_PREFIX` variable
+    /// let config = ViteConfig::load(Some("tests/vite-rust.json"))
+    ///     .unwrap()
+    ///     .set_server_host("http://localhost:5174");
+    ///
+    /// assert_eq!(config.prefix.as_deref(), Some("bundle"));
+    /// assert_eq!(config.server_host.as_deref(), Some("http://localhost:5174"));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if an environment variable holds an invalid value, or if
+    /// the file can't be read or parsed.
+    pub fn load(path: Option<impl AsRef<Path>>) -> Result<Self, ViteError> {
+        ViteConfig::load_with(path, |name| env::var(name).ok())
+    }
+
+    fn load_with(
+        path: Option<impl AsRef<Path>>,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ViteError> {
+        let mut config = ViteConfig::from_env_with(lookup)?;
+        if let Some(path) = path {
+            ConfigLayer::from_file(path.as_ref())?.apply(&mut config);
+        }
+
+        Ok(config)
+    }
+
+    /// Creates a `ViteConfig` with default values overridden by the build settings
+    /// in the JSON descriptor emitted by the Vite plugin (see `vite-rs-plugin`'s
+    /// `descriptorFile` option), so that they aren't duplicated on the Rust side.
+    ///
+    /// The descriptor's fields are mapped as follows:
+    /// - `manifestPath` to `manifest_path`, resolved relatively to the descriptor;
+    /// - `base` to `prefix`, or to `app_url` if it's an absolute URL;
+    /// - `entrypoints` to `entrypoints`;
+    /// - `devServerUrl` to `server_host`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use vite_rust::ViteConfig;
+    ///
+    /// let config = ViteConfig::from_descriptor("tests/descriptor/vite-rust.json").unwrap();
+    ///
+    /// assert_eq!(config.manifest_path.as_deref(), Some("tests/descriptor/../test-manifest.json"));
+    /// assert_eq!(config.prefix.as_deref(), Some("/bundle/"));
+    /// assert_eq!(config.server_host.as_deref(), Some("http://localhost:5174"));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if the descriptor can't be read or parsed.
+    pub fn from_descriptor(path: impl AsRef<Path>) -> Result<Self, ViteError> {
+        ViteConfig::default().with_descriptor(path)
+    }
+
+    /// Does the same as [`ViteConfig::from_descriptor`], but overrides this config's
+    /// values instead of the default ones.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if the descriptor can't be read or parsed.
+    pub fn with_descriptor(mut self, path: impl AsRef<Path>) -> Result<Self, ViteError> {
+        ConfigLayer::from_descriptor(path.as_ref())?.apply(&mut self);
+        Ok(self)
+    }
+
+    /// Checks the whole config at once, before any IO or network happens, and
+    /// returns it back if it's valid. [`Vite::new`] calls it as its first step.
+    ///
+    /// # Errors
+    /// Returns every [`ConfigProblem`] found, one per misconfigured field.
+    ///
+    /// # Example
+    /// ```rust
+    /// use vite_rust::{ViteConfig, ViteMode};
+    ///
+    /// let problems = ViteConfig::default()
+    ///     .set_force_mode(ViteMode::Manifest)
+    ///     .set_server_host("localhost:5173")
+    ///     .set_prefix("https://cdn.example.com/assets")
+    ///     .build()
+    ///     .unwrap_err();
+    ///
+    /// let fields = problems.iter().map(|problem| problem.field()).collect::<Vec<_>>();
+    /// assert_eq!(fields, ["manifest_path", "entrypoints", "server_host", "prefix"]);
+    /// ```
+    ///
+    /// [`Vite::new`]: crate::Vite::new
+    pub fn build(self) -> Result<Self, Vec<ConfigProblem>> {
+        let problems = self.validate();
+
+        match problems.is_empty() {
+            true => Ok(self),
+            false => Err(problems),
+        }
+    }
+
+    fn validate(&self) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+
+        match &self.manifest_path {
+            Some(manifest_path) if manifest_path.trim().is_empty() => {
+                problems.push(ConfigProblem::new("manifest_path", "must not be empty."))
+            }
+            None if self.force_mode == Some(ViteMode::Manifest) => {
+                problems.push(ConfigProblem::new(
+                    "manifest_path",
+                    "is required when `force_mode` is Manifest.",
+                ))
+            }
+            _ => {}
+        }
+
+        match &self.entrypoints {
+            Some(entrypoints) if entrypoints.is_empty() => problems.push(ConfigProblem::new(
+                "entrypoints",
+                "must not be an empty list. Set it to `None` to use the manifest's entries instead.",
+            )),
+            Some(entrypoints) if entrypoints.iter().any(|entry| entry.trim().is_empty()) => {
+                problems.push(ConfigProblem::new("entrypoints", "must not contain empty paths."))
+            }
+            None if self.manifest_path.is_none() => problems.push(ConfigProblem::new(
+                "entrypoints",
+                "must be set when there is no `manifest_path` to discover them from.",
+            )),
+            _ => {}
+        }
+
+        if self.force_mode == Some(ViteMode::Development) && !self.enable_dev_server {
+            problems.push(ConfigProblem::new(
+                "force_mode",
+                "can't be Development when `enable_dev_server` is false.",
+            ));
+        }
+
+        if let Some(server_host) = &self.server_host {
+            if let Err(reason) = check_url(server_host) {
+                problems.push(ConfigProblem::new(
+                    "server_host",
+                    format!(r#""{server_host}" is not a valid URL: {reason}"#),
+                ));
+            }
+        }
+
+        if let Some(prefix) = &self.prefix {
+            if prefix.contains("://") || prefix.starts_with("//") {
+                problems.push(ConfigProblem::new(
+                    "prefix",
+                    format!(
+                        r#""{prefix}" must be a path, not a URL. Set the domain in `app_url` instead."#
+                    ),
+                ));
+            } else if prefix.chars().any(char::is_whitespace) {
+                problems.push(ConfigProblem::new(
+                    "prefix",
+                    format!(r#""{prefix}" must not contain whitespaces."#),
+                ));
+            }
+        }
+
+        if let Some(app_url) = self.app_url.as_ref().filter(|app_url| !app_url.is_empty()) {
+            if let Err(reason) = check_url(app_url) {
+                problems.push(ConfigProblem::new(
+                    "app_url",
+                    format!(r#""{app_url}" is not a valid URL: {reason}"#),
+                ));
+            }
+        }
+
+        if let Some(hot_file_path) = &self.hot_file_path {
+            if hot_file_path.trim().is_empty() {
+                problems.push(ConfigProblem::new("hot_file_path", "must not be empty."));
+            }
+        }
+
+        if self.dev_server_ca_path.is_some() && self.pinned_dev_server_cert_path.is_some() {
+            problems.push(ConfigProblem::new(
+                "pinned_dev_server_cert_path",
+                "can't be set along with `dev_server_ca_path`, since it replaces every other trusted certificate.",
+            ));
+        }
+
+        if self.pinned_dev_server_cert_path.is_some() && self.accept_invalid_dev_server_certs {
+            problems.push(ConfigProblem::new(
+                "accept_invalid_dev_server_certs",
+                "can't be set along with `pinned_dev_server_cert_path`, since accepting invalid certificates defeats the pinning.",
+            ));
+        }
+
+        for (field, path) in [
+            ("dev_server_ca_path", &self.dev_server_ca_path),
+            (
+                "pinned_dev_server_cert_path",
+                &self.pinned_dev_server_cert_path,
+            ),
+        ] {
+            if path.as_ref().is_some_and(|path| path.trim().is_empty()) {
+                problems.push(ConfigProblem::new(field, "must not be empty."));
+            }
+        }
+
+        if let Some(server_host) = self
+            .server_host
+            .as_ref()
+            .filter(|_| self.accept_invalid_dev_server_certs)
+        {
+            if !is_local_url(server_host) {
+                problems.push(ConfigProblem::new(
+                    "accept_invalid_dev_server_certs",
+                    format!(r#"is only allowed for a localhost dev-server, but `server_host` is "{server_host}"."#),
+                ));
+            }
+        }
+
+        if self
+            .style_sheet_extensions
+            .iter()
+            .any(|extension| extension.trim_start_matches('.').trim().is_empty())
+        {
+            problems.push(ConfigProblem::new(
+                "style_sheet_extensions",
+                "must not contain empty extensions.",
+            ));
+        }
+
+        for (field, duration) in [
+            ("heart_beat_timeout", self.heart_beat_timeout),
+            ("heart_beat_deadline", self.heart_beat_deadline),
+        ] {
+            if duration == Some(Duration::ZERO) {
+                problems.push(ConfigProblem::new(field, "must be longer than zero."));
+            }
+        }
+
+        if self.heart_beat_backoff_factor == 0 {
+            problems.push(ConfigProblem::new(
+                "heart_beat_backoff_factor",
+                "must be at least 1.",
+            ));
+        }
+
+        if self.production_predicate.is_none() {
+            if self
+                .production_env_vars
+                .iter()
+                .any(|var| var.trim().is_empty())
+            {
+                problems.push(ConfigProblem::new(
+                    "production_env_vars",
+                    "must not contain empty names.",
+                ));
+            }
+
+            if !self.production_env_vars.is_empty() && self.production_env_values.is_empty() {
+                problems.push(ConfigProblem::new(
+                    "production_env_values",
+                    "must not be empty when `production_env_vars` is set.",
+                ));
+            }
+        }
+
+        problems
+    }
+
+    pub fn set_manifest_path(mut self, manifest_path: impl Into<String>) -> Self {
+        self.manifest_path = Some(manifest_path.into());
         self
     }
 
-    pub fn set_entrypoints(mut self, entrypoints: Vec<&'a str>) -> Self {
-        self.entrypoints = Some(entrypoints);
+    pub fn set_entrypoints<E, S>(mut self, entrypoints: E) -> Self
+    where
+        E: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.entrypoints = Some(entrypoints.into_iter().map(Into::into).collect());
         self
     }
 
@@ -147,8 +658,8 @@ impl<'a> ViteConfig<'a> {
         self.force_mode = Some(mode);
         self
     }
-    pub fn set_server_host(mut self, server_host: &'a str) -> Self {
-        self.server_host = Some(server_host);
+    pub fn set_server_host(mut self, server_host: impl Into<String>) -> Self {
+        self.server_host = Some(server_host.into());
         self
     }
 
@@ -157,6 +668,41 @@ impl<'a> ViteConfig<'a> {
         self
     }
 
+    pub fn set_heart_beat_timeout(mut self, timeout: Duration) -> Self {
+        self.heart_beat_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the delay before the first heart beat retry, and by how much it's
+    /// multiplied after each retry.
+    pub fn set_heart_beat_backoff(mut self, initial_delay: Duration, factor: u32) -> Self {
+        self.heart_beat_initial_delay = Some(initial_delay);
+        self.heart_beat_backoff_factor = factor;
+        self
+    }
+
+    pub fn set_heart_beat_jitter(mut self, enabled: bool) -> Self {
+        self.heart_beat_jitter = enabled;
+        self
+    }
+
+    pub fn set_heart_beat_deadline(mut self, deadline: Duration) -> Self {
+        self.heart_beat_deadline = Some(deadline);
+        self
+    }
+
+    pub(crate) fn heart_beat_options(&self, tls: Arc<DevServerTls>) -> HeartBeatOptions {
+        HeartBeatOptions {
+            timeout: self.heart_beat_timeout,
+            retries: self.heart_beat_retries_limit.unwrap_or(5),
+            initial_delay: self.heart_beat_initial_delay,
+            backoff_factor: self.heart_beat_backoff_factor,
+            jitter: self.heart_beat_jitter,
+            deadline: self.heart_beat_deadline,
+            tls,
+        }
+    }
+
     pub fn without_heart_beat_check(mut self) -> Self {
         self.use_heart_beat_check = false;
         self
@@ -167,18 +713,145 @@ impl<'a> ViteConfig<'a> {
         self
     }
 
-    pub fn set_prefix(mut self, prefix: &'a str) -> Self {
-        self.prefix = Some(prefix);
+    pub fn set_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn set_app_url(mut self, app_url: impl Into<String>) -> Self {
+        self.app_url = Some(app_url.into());
+        self
+    }
+
+    pub fn set_subresource_integrity(mut self, enabled: bool) -> Self {
+        self.subresource_integrity = enabled;
         self
     }
 
-    pub fn set_app_url(mut self, app_url: &'a str) -> Self {
-        self.app_url = Some(app_url);
+    pub fn set_proxy_dev_server(mut self, enabled: bool) -> Self {
+        self.proxy_dev_server = enabled;
         self
     }
+
+    pub fn set_backend_hmr_events(mut self, enabled: bool) -> Self {
+        self.backend_hmr_events = enabled;
+        self
+    }
+
+    pub fn set_dev_server_ca_path(mut self, ca_path: impl Into<String>) -> Self {
+        self.dev_server_ca_path = Some(ca_path.into());
+        self
+    }
+
+    pub fn set_pinned_dev_server_cert_path(mut self, cert_path: impl Into<String>) -> Self {
+        self.pinned_dev_server_cert_path = Some(cert_path.into());
+        self
+    }
+
+    /// Accepts invalid certificates from the dev-server, as long as it runs on
+    /// localhost.
+    pub fn set_accept_invalid_dev_server_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_dev_server_certs = accept;
+        self
+    }
+
+    pub fn set_prefetch_depth(mut self, depth: u8) -> Self {
+        self.prefetch_depth = Some(depth);
+        self
+    }
+
+    /// Replaces the extensions of stylesheet entrypoints, e.g. `[".css", ".scss"]`.
+    /// The leading dot is optional.
+    pub fn set_style_sheet_extensions<X, S>(mut self, extensions: X) -> Self
+    where
+        X: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.style_sheet_extensions = extensions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn set_hot_file_path(mut self, hot_file_path: impl Into<String>) -> Self {
+        self.hot_file_path = Some(hot_file_path.into());
+        self
+    }
+
+    pub fn set_production_env_vars<V, S>(mut self, vars: V) -> Self
+    where
+        V: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.production_env_vars = vars.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn set_production_env_values<V, S>(mut self, values: V) -> Self
+    where
+        V: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.production_env_values = values.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn set_production_predicate(mut self, predicate: fn() -> bool) -> Self {
+        self.production_predicate = Some(ProductionPredicate(predicate));
+        self
+    }
+
+    /// Whether the application runs in a production-like environment, as told by
+    /// `production_predicate` if set, or by any of the `production_env_vars` holding
+    /// one of the `production_env_values` otherwise. Logs what made the decision.
+    ///
+    /// # Example
+    /// ```rust
+    /// use vite_rust::ViteConfig;
+    ///
+    /// std::env::set_var("MY_APP_STAGE", "staging");
+    ///
+    /// let config = ViteConfig::default()
+    ///     .set_production_env_vars(["MY_APP_STAGE"])
+    ///     .set_production_env_values(["production", "staging"]);
+    ///
+    /// assert!(config.is_production());
+    /// # std::env::remove_var("MY_APP_STAGE");
+    /// ```
+    pub fn is_production(&self) -> bool {
+        self.is_production_with(|var| env::var(var).ok())
+    }
+
+    fn is_production_with(&self, lookup: impl Fn(&str) -> Option<String>) -> bool {
+        if let Some(ProductionPredicate(predicate)) = self.production_predicate {
+            let is_production = predicate();
+            log::info!(
+                "The production predicate considers this {} environment.",
+                if is_production {
+                    "a production"
+                } else {
+                    "not a production"
+                }
+            );
+
+            return is_production;
+        }
+
+        let trigger = self.production_env_vars.iter().find_map(|var| {
+            lookup(var)
+                .filter(|value| self.production_env_values.contains(value))
+                .map(|value| (var, value))
+        });
+
+        match trigger {
+            Some((var, value)) => {
+                log::info!("{var}={value} marks this as a production environment.");
+                true
+            }
+            None => false,
+        }
+    }
 }
 
-impl Default for ViteConfig<'_> {
+impl Default for ViteConfig {
     /// Create a `ViteConfig` instance.
     ///
     /// You can create your config by directly instantiating the struct, or
@@ -187,18 +860,39 @@ impl Default for ViteConfig<'_> {
     ///
     /// # Example
     /// ```rust
-    /// use vite_rust::ViteConfig;
+    /// use std::time::Duration;
+    /// use vite_rust::{
+    ///     ViteConfig, DEFAULT_HEART_BEAT_DEADLINE, DEFAULT_HEART_BEAT_TIMEOUT,
+    ///     DEFAULT_PRODUCTION_ENV_VARS, DEFAULT_STYLE_SHEET_EXTENSIONS,
+    /// };
     ///
     /// let manual_config = ViteConfig {
-    ///     manifest_path: Some("path/to/manifest.json"),
+    ///     manifest_path: Some("path/to/manifest.json".to_string()),
     ///     entrypoints: None, // Vite can discover them by itself
     ///     force_mode: None, // Vite can discover it too
     ///     use_heart_beat_check: true,
     ///     enable_dev_server: true,
-    ///     server_host: Some("http://localhost:5173"),
+    ///     server_host: Some("http://localhost:5173".to_string()),
     ///     heart_beat_retries_limit: Some(5),
+    ///     heart_beat_timeout: Some(DEFAULT_HEART_BEAT_TIMEOUT),
+    ///     heart_beat_initial_delay: Some(Duration::from_millis(100)),
+    ///     heart_beat_backoff_factor: 2,
+    ///     heart_beat_jitter: true,
+    ///     heart_beat_deadline: Some(DEFAULT_HEART_BEAT_DEADLINE),
     ///     prefix: None,
     ///     app_url: None,
+    ///     subresource_integrity: false,
+    ///     prefetch_depth: None,
+    ///     style_sheet_extensions: DEFAULT_STYLE_SHEET_EXTENSIONS.map(String::from).to_vec(),
+    ///     hot_file_path: None,
+    ///     proxy_dev_server: false,
+    ///     backend_hmr_events: false,
+    ///     dev_server_ca_path: None,
+    ///     pinned_dev_server_cert_path: None,
+    ///     accept_invalid_dev_server_certs: false,
+    ///     production_env_vars: DEFAULT_PRODUCTION_ENV_VARS.map(String::from).to_vec(),
+    ///     production_env_values: vec!["production".to_string()],
+    ///     production_predicate: None,
     /// };
     ///
     /// let with_defaults_config = ViteConfig::default().set_manifest_path("path/to/manifest.json");
@@ -211,43 +905,634 @@ impl Default for ViteConfig<'_> {
             entrypoints: None,
             manifest_path: None,
             force_mode: None,
-            server_host: Some("http://localhost:5173"),
+            server_host: Some("http://localhost:5173".to_string()),
             use_heart_beat_check: true,
             heart_beat_retries_limit: Some(5),
+            heart_beat_timeout: Some(DEFAULT_HEART_BEAT_TIMEOUT),
+            heart_beat_initial_delay: Some(Duration::from_millis(100)),
+            heart_beat_backoff_factor: 2,
+            heart_beat_jitter: true,
+            heart_beat_deadline: Some(DEFAULT_HEART_BEAT_DEADLINE),
             prefix: None,
             app_url: None,
+            subresource_integrity: false,
+            prefetch_depth: None,
+            style_sheet_extensions: DEFAULT_STYLE_SHEET_EXTENSIONS.map(String::from).to_vec(),
+            hot_file_path: None,
+            proxy_dev_server: false,
+            backend_hmr_events: false,
+            dev_server_ca_path: None,
+            pinned_dev_server_cert_path: None,
+            accept_invalid_dev_server_certs: false,
+            production_env_vars: DEFAULT_PRODUCTION_ENV_VARS.map(String::from).to_vec(),
+            production_env_values: DEFAULT_PRODUCTION_ENV_VALUES.map(String::from).to_vec(),
+            production_predicate: None,
+        }
+    }
+}
+
+/// A problem found in a [`ViteConfig`] field by [`ViteConfig::build`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ConfigProblem {
+    field: &'static str,
+    message: String,
+}
+
+impl ConfigProblem {
+    fn new(field: &'static str, message: impl Into<String>) -> Self {
+        ConfigProblem {
+            field,
+            message: message.into(),
+        }
+    }
+
+    /// The name of the misconfigured field, e.g. `"server_host"`.
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` {}", self.field, self.message)
+    }
+}
+
+/// Checks whether the given URL points to the local machine.
+fn is_local_url(url: &str) -> bool {
+    split_url(url).is_ok_and(|(_, authority, _, _)| is_local_host(host_name(authority)))
+}
+
+/// Checks that the given string is an absolute `http` or `https` URL with a host,
+/// returning the reason why it isn't otherwise.
+fn check_url(url: &str) -> Result<(), &'static str> {
+    let authority = match url
+        .strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"))
+    {
+        Some(rest) => rest.split('/').next().unwrap_or_default(),
+        None => return Err("it must start with http:// or https://."),
+    };
+
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(ipv6) => match ipv6.split_once(']') {
+            Some((host, rest)) => (host, rest.strip_prefix(':')),
+            None => return Err("it must have a valid host."),
+        },
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+
+    if host.is_empty() || host.chars().any(char::is_whitespace) {
+        return Err("it must have a valid host.");
+    }
+
+    if port.is_some_and(|port| port.parse::<u16>().is_err()) {
+        return Err("its port must be a number between 0 and 65535.");
+    }
+
+    Ok(())
+}
+
+/// A partial [`ViteConfig`], read from the environment or a config file, whose
+/// set fields override the ones of a config.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigLayer {
+    manifest_path: Option<String>,
+    entrypoints: Option<Vec<String>>,
+    force_mode: Option<ViteMode>,
+    use_heart_beat_check: Option<bool>,
+    heart_beat_retries_limit: Option<u8>,
+    #[serde(default, deserialize_with = "deserialize_millis")]
+    heart_beat_timeout: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_millis")]
+    heart_beat_initial_delay: Option<Duration>,
+    heart_beat_backoff_factor: Option<u32>,
+    heart_beat_jitter: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_millis")]
+    heart_beat_deadline: Option<Duration>,
+    enable_dev_server: Option<bool>,
+    server_host: Option<String>,
+    prefix: Option<String>,
+    app_url: Option<String>,
+    subresource_integrity: Option<bool>,
+    prefetch_depth: Option<u8>,
+    style_sheet_extensions: Option<Vec<String>>,
+    hot_file_path: Option<String>,
+    proxy_dev_server: Option<bool>,
+    backend_hmr_events: Option<bool>,
+    dev_server_ca_path: Option<String>,
+    pinned_dev_server_cert_path: Option<String>,
+    accept_invalid_dev_server_certs: Option<bool>,
+    production_env_vars: Option<Vec<String>>,
+    production_env_values: Option<Vec<String>>,
+}
+
+impl ConfigLayer {
+    fn from_env(lookup: &dyn Fn(&str) -> Option<String>) -> Result<Self, ViteError> {
+        Ok(ConfigLayer {
+            manifest_path: env_var(lookup, "MANIFEST_PATH"),
+            entrypoints: env_var(lookup, "ENTRYPOINTS").map(|entrypoints| split_list(&entrypoints)),
+            force_mode: parse_env_var(lookup, "FORCE_MODE", |value| {
+                match value.to_lowercase().as_str() {
+                    "development" => Some(ViteMode::Development),
+                    "manifest" => Some(ViteMode::Manifest),
+                    _ => None,
+                }
+            })?,
+            use_heart_beat_check: parse_env_var(lookup, "USE_HEART_BEAT_CHECK", parse_bool)?,
+            heart_beat_retries_limit: parse_env_var(lookup, "HEART_BEAT_RETRIES_LIMIT", |value| {
+                value.parse().ok()
+            })?,
+            heart_beat_timeout: parse_env_var(lookup, "HEART_BEAT_TIMEOUT", parse_millis)?,
+            heart_beat_initial_delay: parse_env_var(
+                lookup,
+                "HEART_BEAT_INITIAL_DELAY",
+                parse_millis,
+            )?,
+            heart_beat_backoff_factor: parse_env_var(
+                lookup,
+                "HEART_BEAT_BACKOFF_FACTOR",
+                |value| value.parse().ok(),
+            )?,
+            heart_beat_jitter: parse_env_var(lookup, "HEART_BEAT_JITTER", parse_bool)?,
+            heart_beat_deadline: parse_env_var(lookup, "HEART_BEAT_DEADLINE", parse_millis)?,
+            enable_dev_server: parse_env_var(lookup, "ENABLE_DEV_SERVER", parse_bool)?,
+            server_host: env_var(lookup, "SERVER_HOST"),
+            prefix: env_var(lookup, "PREFIX"),
+            app_url: env_var(lookup, "APP_URL"),
+            subresource_integrity: parse_env_var(lookup, "SUBRESOURCE_INTEGRITY", parse_bool)?,
+            prefetch_depth: parse_env_var(lookup, "PREFETCH_DEPTH", |value| value.parse().ok())?,
+            style_sheet_extensions: env_var(lookup, "STYLE_SHEET_EXTENSIONS")
+                .map(|extensions| split_list(&extensions)),
+            hot_file_path: env_var(lookup, "HOT_FILE_PATH"),
+            proxy_dev_server: parse_env_var(lookup, "PROXY_DEV_SERVER", parse_bool)?,
+            backend_hmr_events: parse_env_var(lookup, "BACKEND_HMR_EVENTS", parse_bool)?,
+            dev_server_ca_path: env_var(lookup, "DEV_SERVER_CA_PATH"),
+            pinned_dev_server_cert_path: env_var(lookup, "PINNED_DEV_SERVER_CERT_PATH"),
+            accept_invalid_dev_server_certs: parse_env_var(
+                lookup,
+                "ACCEPT_INVALID_DEV_SERVER_CERTS",
+                parse_bool,
+            )?,
+            production_env_vars: env_var(lookup, "PRODUCTION_ENV_VARS")
+                .map(|vars| split_list(&vars)),
+            production_env_values: env_var(lookup, "PRODUCTION_ENV_VALUES")
+                .map(|values| split_list(&values)),
+        })
+    }
+
+    fn from_file(path: &Path) -> Result<Self, ViteError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => {
+                return Err(ViteError::from_io(
+                    format!("Failed to read config file {}: {err}", path.display()),
+                    path,
+                    err,
+                ))
+            }
+        };
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => serde_json::from_str(&content).map_err(|err| {
+                ViteError::with_source(
+                    format!("Failed to parse config file {}: {err}", path.display()),
+                    ViteErrorKind::JsonParse {
+                        line: err.line(),
+                        column: err.column(),
+                    },
+                    err,
+                )
+            }),
+            #[cfg(feature = "toml")]
+            Some("toml") => toml::from_str(&content).map_err(|err| {
+                ViteError::with_source(
+                    format!("Failed to parse config file {}: {err}", path.display()),
+                    ViteErrorKind::InvalidConfig,
+                    err,
+                )
+            }),
+            _ => Err(ViteError::new(
+                format!(
+                    "Unsupported config file format: {}. Expected a .json{} file.",
+                    path.display(),
+                    if cfg!(feature = "toml") {
+                        " or .toml"
+                    } else {
+                        ""
+                    }
+                ),
+                ViteErrorKind::InvalidConfig,
+            )),
+        }
+    }
+
+    fn from_descriptor(path: &Path) -> Result<Self, ViteError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) => {
+                return Err(ViteError::from_io(
+                    format!("Failed to read build descriptor {}: {err}", path.display()),
+                    path,
+                    err,
+                ))
+            }
+        };
+
+        let descriptor: BuildDescriptor = serde_json::from_str(&content).map_err(|err| {
+            ViteError::with_source(
+                format!("Failed to parse build descriptor {}: {err}", path.display()),
+                ViteErrorKind::JsonParse {
+                    line: err.line(),
+                    column: err.column(),
+                },
+                err,
+            )
+        })?;
+
+        let manifest_path = descriptor.manifest_path.map(|manifest_path| {
+            match path
+                .parent()
+                .filter(|_| Path::new(&manifest_path).is_relative())
+            {
+                Some(descriptor_dir) => descriptor_dir
+                    .join(manifest_path)
+                    .to_string_lossy()
+                    .to_string(),
+                None => manifest_path,
+            }
+        });
+
+        let (prefix, app_url) = match descriptor.base {
+            Some(base) if check_url(&base).is_ok() => (None, Some(base)),
+            Some(base) if !["", "/", "./"].contains(&base.as_str()) => (Some(base), None),
+            _ => (None, None),
+        };
+
+        Ok(ConfigLayer {
+            manifest_path,
+            entrypoints: descriptor.entrypoints,
+            server_host: descriptor.dev_server_url,
+            prefix,
+            app_url,
+            ..Default::default()
+        })
+    }
+
+    fn apply(self, config: &mut ViteConfig) {
+        macro_rules! override_fields {
+            ($($field:ident),*) => {
+                $(if let Some(value) = self.$field {
+                    config.$field = value;
+                })*
+            };
         }
+
+        macro_rules! override_optional_fields {
+            ($($field:ident),*) => {
+                $(if self.$field.is_some() {
+                    config.$field = self.$field;
+                })*
+            };
+        }
+
+        override_fields!(
+            use_heart_beat_check,
+            enable_dev_server,
+            subresource_integrity,
+            heart_beat_backoff_factor,
+            heart_beat_jitter,
+            style_sheet_extensions,
+            proxy_dev_server,
+            backend_hmr_events,
+            accept_invalid_dev_server_certs,
+            production_env_vars,
+            production_env_values
+        );
+        override_optional_fields!(
+            manifest_path,
+            entrypoints,
+            force_mode,
+            heart_beat_retries_limit,
+            heart_beat_timeout,
+            heart_beat_initial_delay,
+            heart_beat_deadline,
+            server_host,
+            prefix,
+            app_url,
+            prefetch_depth,
+            hot_file_path,
+            dev_server_ca_path,
+            pinned_dev_server_cert_path
+        );
+    }
+}
+
+/// The build settings written by the Vite plugin, which has them from `vite.config.ts`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildDescriptor {
+    manifest_path: Option<String>,
+    base: Option<String>,
+    entrypoints: Option<Vec<String>>,
+    dev_server_url: Option<String>,
+}
+
+const ENV_PREFIX: &str = "VITE_RUST_";
+
+fn env_var(lookup: &dyn Fn(&str) -> Option<String>, name: &str) -> Option<String> {
+    lookup(&format!("{ENV_PREFIX}{name}"))
+}
+
+fn parse_env_var<T>(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    name: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<Option<T>, ViteError> {
+    match env_var(lookup, name) {
+        None => Ok(None),
+        Some(value) => match parse(value.trim()) {
+            Some(parsed) => Ok(Some(parsed)),
+            None => Err(ViteError::new(
+                format!(r#"Invalid value "{value}" for {ENV_PREFIX}{name} environment variable."#),
+                ViteErrorKind::InvalidConfig,
+            )),
+        },
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
     }
 }
 
+fn parse_millis(value: &str) -> Option<Duration> {
+    value.parse().ok().map(Duration::from_millis)
+}
+
+/// Deserializes a duration given in milliseconds.
+fn deserialize_millis<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+}
+
+/// Splits a comma-separated list, skipping empty items.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
-    use std::env;
+    use std::collections::HashMap;
+    use std::time::Duration;
 
-    use crate::{config::is_production, ViteMode};
+    use crate::error::ViteErrorKind;
+    use crate::test_utils::TempFile;
+    use crate::{ViteConfig, ViteMode};
 
+    #[cfg(feature = "async")]
     #[tokio::test]
     async fn test_discover() {
         let host = "http://localhost:3000";
-        let hb_retries = 1;
+        let hb_options = ViteConfig::default()
+            .set_heart_beat_retries_limit(1)
+            .heart_beat_options(Default::default());
 
         assert_eq!(
             ViteMode::Development,
-            ViteMode::discover(false, true, host, hb_retries).await
+            ViteMode::discover(false, true, false, host, &hb_options)
+                .await
+                .unwrap()
+        );
+
+        assert_eq!(
+            ViteMode::Manifest,
+            ViteMode::discover(true, false, false, host, &hb_options)
+                .await
+                .unwrap()
         );
 
         assert_eq!(
             ViteMode::Manifest,
-            ViteMode::discover(true, false, host, hb_retries).await
+            ViteMode::discover(true, true, true, host, &hb_options)
+                .await
+                .unwrap()
         );
     }
 
     #[test]
     fn test_is_production() {
-        env::set_var("__TEST_APP_ENV", "production");
-        assert!(is_production());
+        let config = ViteConfig::default().set_production_env_vars(["APP_ENV"]);
+        let lookup =
+            |value: &'static str| move |var: &str| (var == "APP_ENV").then(|| value.to_string());
 
-        env::remove_var("__TEST_APP_ENV");
-        assert!(!is_production());
+        assert!(config.is_production_with(lookup("production")));
+        assert!(!config.is_production_with(lookup("staging")));
+
+        let config = config.set_production_env_values(["production", "staging"]);
+        assert!(config.is_production_with(lookup("staging")));
+        assert!(!config.is_production_with(|_| None));
+
+        let config = config.set_production_predicate(|| true);
+        assert!(config.is_production());
+
+        let problems = ViteConfig::default()
+            .set_manifest_path("tests/test-manifest.json")
+            .set_production_env_vars(["APP_ENV", " "])
+            .set_production_env_values(Vec::<String>::new())
+            .build()
+            .unwrap_err();
+
+        let fields = problems
+            .iter()
+            .map(|problem| problem.field())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["production_env_vars", "production_env_values"], fields);
+    }
+
+    #[test]
+    fn test_from_file() {
+        let config = ViteConfig::from_file("tests/vite-rust.json").unwrap();
+
+        assert_eq!(
+            ViteConfig::default()
+                .set_manifest_path("tests/test-manifest.json")
+                .set_entrypoints(["views/foo.js", "views/bar.js"])
+                .set_force_mode(ViteMode::Manifest)
+                .set_prefix("bundle"),
+            config
+        );
+
+        #[cfg(feature = "toml")]
+        assert_eq!(
+            config,
+            ViteConfig::from_file("tests/vite-rust.toml").unwrap()
+        );
+
+        let error = ViteConfig::from_file("tests/test-manifest.json").unwrap_err();
+        assert!(matches!(error.kind(), ViteErrorKind::JsonParse { .. }));
+    }
+
+    #[test]
+    fn test_from_env_and_precedence() {
+        let mut vars = HashMap::from([
+            ("VITE_RUST_SERVER_HOST", "http://localhost:3000"),
+            ("VITE_RUST_PREFIX", "from-env"),
+            ("VITE_RUST_ENTRYPOINTS", "src/main.ts, src/admin.ts"),
+            ("VITE_RUST_SUBRESOURCE_INTEGRITY", "true"),
+            ("VITE_RUST_HEART_BEAT_TIMEOUT", "500"),
+        ]);
+
+        let config =
+            ViteConfig::from_env_with(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+        assert_eq!(Some("http://localhost:3000"), config.server_host.as_deref());
+        assert_eq!(Some("from-env"), config.prefix.as_deref());
+        assert_eq!(
+            Some(vec!["src/main.ts".to_string(), "src/admin.ts".to_string()]),
+            config.entrypoints
+        );
+        assert!(config.subresource_integrity);
+        assert_eq!(Some(Duration::from_millis(500)), config.heart_beat_timeout);
+
+        let config = ViteConfig::load_with(Some("tests/vite-rust.json"), |name| {
+            vars.get(name).map(|v| v.to_string())
+        })
+        .unwrap()
+        .set_app_url("http://foo.baz");
+        assert_eq!(Some("http://localhost:3000"), config.server_host.as_deref());
+        assert_eq!(Some("bundle"), config.prefix.as_deref());
+        assert_eq!(Some("http://foo.baz"), config.app_url.as_deref());
+
+        vars.insert("VITE_RUST_PREFETCH_DEPTH", "many");
+        let error =
+            ViteConfig::from_env_with(|name| vars.get(name).map(|v| v.to_string())).unwrap_err();
+        assert_eq!(&ViteErrorKind::InvalidConfig, error.kind());
+    }
+
+    #[test]
+    fn test_build() {
+        let config = ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+            .set_server_host("http://[::1]:5173/")
+            .set_app_url("https://example.com")
+            .set_prefix("/bundle/");
+
+        assert_eq!(Ok(config.clone()), config.build());
+
+        let problems = ViteConfig::default()
+            .set_entrypoints(Vec::<String>::new())
+            .set_server_host("http://localhost:75173")
+            .set_app_url("example.com")
+            .set_force_mode(ViteMode::Development)
+            .set_heart_beat_backoff(Duration::from_millis(100), 0)
+            .without_dev_server()
+            .build()
+            .unwrap_err();
+
+        let fields = problems
+            .iter()
+            .map(|problem| problem.field())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                "entrypoints",
+                "force_mode",
+                "server_host",
+                "app_url",
+                "heart_beat_backoff_factor"
+            ],
+            fields
+        );
+        assert_eq!(
+            r#"`app_url` "example.com" is not a valid URL: it must start with http:// or https://."#,
+            problems[3].to_string()
+        );
+    }
+
+    #[test]
+    fn test_build_tls_options() {
+        let config = ViteConfig::default()
+            .set_entrypoints(["src/main.ts"])
+            .set_server_host("https://localhost:5173")
+            .set_dev_server_ca_path("tests/tls/localhost.pem")
+            .set_accept_invalid_dev_server_certs(true);
+
+        assert_eq!(Ok(config.clone()), config.build());
+
+        let problems = ViteConfig::default()
+            .set_entrypoints(["src/main.ts"])
+            .set_server_host("https://dev.example.com")
+            .set_dev_server_ca_path("")
+            .set_pinned_dev_server_cert_path("tests/tls/localhost.pem")
+            .set_accept_invalid_dev_server_certs(true)
+            .build()
+            .unwrap_err();
+
+        let fields = problems
+            .iter()
+            .map(|problem| problem.field())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                "pinned_dev_server_cert_path",
+                "accept_invalid_dev_server_certs",
+                "dev_server_ca_path",
+                "accept_invalid_dev_server_certs"
+            ],
+            fields
+        );
+
+        let problems = ViteConfig::default()
+            .set_entrypoints(["src/main.ts"])
+            .set_server_host("https://localhost:5173")
+            .set_pinned_dev_server_cert_path("tests/tls/localhost.pem")
+            .set_accept_invalid_dev_server_certs(true)
+            .build()
+            .unwrap_err();
+        assert_eq!(1, problems.len());
+        assert_eq!("accept_invalid_dev_server_certs", problems[0].field());
+    }
+
+    #[test]
+    fn test_with_descriptor() {
+        let descriptor = TempFile::new("descriptor.json");
+        std::fs::write(
+            &descriptor,
+            r#"{ "manifestPath": "/srv/app/dist/.vite/manifest.json", "base": "https://cdn.example.com/" }"#,
+        )
+        .unwrap();
+
+        let config = ViteConfig::default()
+            .set_prefix("bundle")
+            .set_entrypoints(["src/main.ts"])
+            .with_descriptor(&descriptor)
+            .unwrap();
+
+        assert_eq!(
+            Some("/srv/app/dist/.vite/manifest.json"),
+            config.manifest_path.as_deref()
+        );
+        assert_eq!(Some("https://cdn.example.com/"), config.app_url.as_deref());
+        assert_eq!(Some("bundle"), config.prefix.as_deref());
+        assert_eq!(Some(vec!["src/main.ts".to_string()]), config.entrypoints);
     }
 }
@@ -1,16 +1,38 @@
 use serde::Deserialize;
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, path::PathBuf};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+#[non_exhaustive]
 pub enum ViteErrorKind {
+    /// The manifest is unavailable or inconsistent, e.g. a chunk imports
+    /// another one that is missing from it.
     Manifest,
+    /// A required file, such as the `manifest.json`, doesn't exist.
+    MissingFile { path: PathBuf },
+    /// A file exists but couldn't be read.
+    Io,
+    /// The `manifest.json` file isn't valid JSON, or doesn't have the
+    /// expected shape.
+    JsonParse { line: usize, column: usize },
+    /// A configured entrypoint isn't present in the manifest.
+    UnknownEntrypoint { entrypoint: String },
+    /// The given configuration can't be used to start Vite.
+    InvalidConfig,
+    /// The Vite dev-server couldn't be reached, and there is nothing to
+    /// fall back to.
+    DevServerUnreachable { url: String },
+    /// There is no build with the given name in a [`ViteRegistry`].
+    ///
+    /// [`ViteRegistry`]: crate::ViteRegistry
+    UnknownBuild { name: String },
 }
 
 #[derive(Debug, Deserialize)]
-#[allow(unused)]
 pub struct ViteError {
     cause: Box<str>,
     kind: ViteErrorKind,
+    #[serde(skip)]
+    source: Option<Box<dyn Error + Send + Sync + 'static>>,
 }
 
 impl ViteError {
@@ -21,8 +43,41 @@ impl ViteError {
         ViteError {
             cause: cause.to_string().into_boxed_str(),
             kind,
+            source: None,
         }
     }
+
+    /// Creates an error caused by the given lower-level error, which is then
+    /// returned by [`Error::source`].
+    pub fn with_source<T, S>(cause: T, kind: ViteErrorKind, source: S) -> Self
+    where
+        T: ToString,
+        S: Into<Box<dyn Error + Send + Sync + 'static>>,
+    {
+        ViteError {
+            cause: cause.to_string().into_boxed_str(),
+            kind,
+            source: Some(source.into()),
+        }
+    }
+
+    pub fn kind(&self) -> &ViteErrorKind {
+        &self.kind
+    }
+
+    /// Converts an `io::Error` into `MissingFile` if the file doesn't exist, or
+    /// `Io` otherwise.
+    pub(crate) fn from_io<T>(cause: T, path: impl Into<PathBuf>, err: std::io::Error) -> Self
+    where
+        T: ToString,
+    {
+        let kind = match err.kind() {
+            std::io::ErrorKind::NotFound => ViteErrorKind::MissingFile { path: path.into() },
+            _ => ViteErrorKind::Io,
+        };
+
+        ViteError::with_source(cause, kind, err)
+    }
 }
 
 impl fmt::Display for ViteError {
@@ -31,4 +86,37 @@ impl fmt::Display for ViteError {
     }
 }
 
-impl Error for ViteError {}
+impl Error for ViteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ViteError, ViteErrorKind};
+    use std::error::Error;
+    use std::io;
+
+    #[test]
+    fn test_error_source() {
+        let error = ViteError::from_io(
+            "Failed to open manifest",
+            "dist/.vite/manifest.json",
+            io::Error::new(io::ErrorKind::NotFound, "no such file"),
+        );
+
+        assert_eq!(
+            &ViteErrorKind::MissingFile {
+                path: "dist/.vite/manifest.json".into()
+            },
+            error.kind()
+        );
+        assert_eq!("no such file", error.source().unwrap().to_string());
+
+        let error = ViteError::new("Something went wrong", ViteErrorKind::Manifest);
+        assert!(error.source().is_none());
+    }
+}
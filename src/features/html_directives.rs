@@ -1,18 +1,60 @@
 use regex::Regex;
 use std::sync::OnceLock;
 
-use crate::{Vite, ViteError, ViteMode};
+use crate::{Vite, ViteError, ViteMode, ViteRegistry};
 
 static VITE_DIRECTIVE: OnceLock<Regex> = OnceLock::new();
 static VITE_REACT_DIRECTIVE: OnceLock<Regex> = OnceLock::new();
 static VITE_ASSETS_DIRECTIVE: OnceLock<Regex> = OnceLock::new();
 static VITE_HMR_DIRECTIVE: OnceLock<Regex> = OnceLock::new();
+static NAMED_VITE_DIRECTIVE: OnceLock<Regex> = OnceLock::new();
+static NAMED_VITE_REACT_DIRECTIVE: OnceLock<Regex> = OnceLock::new();
+static NAMED_VITE_ASSETS_DIRECTIVE: OnceLock<Regex> = OnceLock::new();
+static NAMED_VITE_HMR_DIRECTIVE: OnceLock<Regex> = OnceLock::new();
 
 pub trait ViteDefaultDirectives {
     fn vite_directive(&self, html: &mut String) -> Result<(), ViteError>;
     fn assets_url_directive(&self, html: &mut String);
     fn react_directive(&self, html: &mut String);
     fn hmr_directive(&self, html: &mut String);
+
+    /// Expands `@vite` directives, adding the given CSP nonce to every generated tag.
+    ///
+    /// Defaults to [`ViteDefaultDirectives::vite_directive`], warning that the nonce is dropped.
+    fn vite_directive_with_nonce(
+        &self,
+        html: &mut String,
+        nonce: Option<&str>,
+    ) -> Result<(), ViteError> {
+        warn_dropped_nonce("vite_directive_with_nonce", nonce);
+        self.vite_directive(html)
+    }
+
+    /// Expands `@vite::react` directives, adding the given CSP nonce to the inline
+    /// React refresh preamble.
+    ///
+    /// Defaults to [`ViteDefaultDirectives::react_directive`], warning that the nonce is dropped.
+    fn react_directive_with_nonce(&self, html: &mut String, nonce: Option<&str>) {
+        warn_dropped_nonce("react_directive_with_nonce", nonce);
+        self.react_directive(html);
+    }
+
+    /// Expands `@vite::hmr` directives, adding the given CSP nonce to the HMR
+    /// client script tag.
+    ///
+    /// Defaults to [`ViteDefaultDirectives::hmr_directive`], warning that the nonce is dropped.
+    fn hmr_directive_with_nonce(&self, html: &mut String, nonce: Option<&str>) {
+        warn_dropped_nonce("hmr_directive_with_nonce", nonce);
+        self.hmr_directive(html);
+    }
+}
+
+fn warn_dropped_nonce(method: &str, nonce: Option<&str>) {
+    if nonce.is_some() {
+        log::warn!(
+            "`ViteDefaultDirectives::{method}` is not implemented, the CSP nonce is dropped."
+        );
+    }
 }
 
 impl ViteDefaultDirectives for Vite {
@@ -32,10 +74,27 @@ impl ViteDefaultDirectives for Vite {
     ///
     /// [`ViteMode`]: crate::ViteMode
     fn vite_directive(&self, html: &mut String) -> Result<(), ViteError> {
+        self.vite_directive_with_nonce(html, None)
+    }
+
+    /// Does the same as [`ViteDefaultDirectives::vite_directive`], adding the given
+    /// CSP nonce to every generated tag.
+    ///
+    /// # Arguments
+    /// * `html`    - A mutable reference to a html string;
+    /// * `nonce`   - The current request's CSP nonce.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` instance if mode is `Manifest` and there is no Manifest.
+    fn vite_directive_with_nonce(
+        &self,
+        html: &mut String,
+        nonce: Option<&str>,
+    ) -> Result<(), ViteError> {
         let regex =
             VITE_DIRECTIVE.get_or_init(|| Regex::new(r"([ \t]*)@vite([ \t]*)(\s|$)").unwrap());
 
-        let tags_or_scripts = self.get_resolved_vite_scripts()?;
+        let tags_or_scripts = self.get_resolved_vite_scripts_for(self.entrypoints(), nonce)?;
 
         *html = regex
             .replace_all(html, |caps: &regex::Captures| {
@@ -72,23 +131,36 @@ impl ViteDefaultDirectives for Vite {
             .to_string();
     }
 
-    /// Expands a `@vite::hmr` directive by the HMR client script tag.
+    /// Expands a `@vite::hmr` directive by the HMR client script tag, followed by
+    /// the backend events snippet if [`ViteConfig::backend_hmr_events`] is enabled.
     /// If mode is [`ViteMode::Manifest`], it will be replaced by an empty
     /// string.
     ///
     /// # Arguments
     /// * `html`    - A mutable reference to a html string.
     ///
+    /// [`ViteConfig::backend_hmr_events`]: crate::ViteConfig::backend_hmr_events
     /// [`ViteMode::Manifest`]: crate::ViteMode::Manifest
     fn hmr_directive(&self, html: &mut String) {
+        self.hmr_directive_with_nonce(html, None);
+    }
+
+    /// Does the same as [`ViteDefaultDirectives::hmr_directive`], adding the given
+    /// CSP nonce to the HMR client script tag.
+    ///
+    /// # Arguments
+    /// * `html`    - A mutable reference to a html string;
+    /// * `nonce`   - The current request's CSP nonce.
+    fn hmr_directive_with_nonce(&self, html: &mut String, nonce: Option<&str>) {
         let regex =
             VITE_HMR_DIRECTIVE.get_or_init(|| Regex::new(r"([ \t]*)@vite::hmr([ \t]*)").unwrap());
 
         *html = regex
-            .replace_all(html, |caps: &regex::Captures| match self.mode {
+            .replace_all(html, |caps: &regex::Captures| match self.mode() {
                 ViteMode::Manifest => "".into(),
                 ViteMode::Development => {
-                    format!("{}{}{}", &caps[1], self.get_hmr_script(), &caps[2])
+                    let script = self.get_hmr_script_with_nonce(nonce);
+                    format!("{}{}{}", &caps[1], script, &caps[2])
                 }
             })
             .to_string();
@@ -103,13 +175,24 @@ impl ViteDefaultDirectives for Vite {
     /// [`ViteMode::Development`]: crate::ViteMode::Development
     /// [react fast refresh]: https://vite.dev/guide/backend-integration
     fn react_directive(&self, html: &mut String) {
+        self.react_directive_with_nonce(html, None);
+    }
+
+    /// Does the same as [`ViteDefaultDirectives::react_directive`], adding the given
+    /// CSP nonce to the inline React refresh preamble.
+    ///
+    /// # Arguments
+    /// * `html`    - A mutable reference to a html string;
+    /// * `nonce`   - The current request's CSP nonce.
+    fn react_directive_with_nonce(&self, html: &mut String, nonce: Option<&str>) {
         let regex = VITE_REACT_DIRECTIVE
             .get_or_init(|| Regex::new(r"([ \t]*)@vite::react([ \t]*)").unwrap());
 
         *html = regex
-            .replace_all(html, |caps: &regex::Captures| match self.mode {
+            .replace_all(html, |caps: &regex::Captures| match self.mode() {
                 ViteMode::Development => {
-                    format!("{}{}{}", &caps[1], self.get_react_script(), &caps[2])
+                    let script = self.get_react_script_with_nonce(nonce);
+                    format!("{}{}{}", &caps[1], script, &caps[2])
                 }
                 ViteMode::Manifest => "".into(),
             })
@@ -117,15 +200,208 @@ impl ViteDefaultDirectives for Vite {
     }
 }
 
+/// Directives taking the name of the [`ViteRegistry`] build they refer to, e.g.
+/// `@vite("admin")`.
+///
+/// Unlike [`ViteDefaultDirectives`], every directive fails with an
+/// [`UnknownBuild`] error if the given build isn't registered.
+///
+/// [`UnknownBuild`]: crate::error::ViteErrorKind::UnknownBuild
+pub trait ViteRegistryDirectives {
+    fn vite_directive(&self, html: &mut String) -> Result<(), ViteError>;
+    fn assets_url_directive(&self, html: &mut String) -> Result<(), ViteError>;
+    fn react_directive(&self, html: &mut String) -> Result<(), ViteError>;
+    fn hmr_directive(&self, html: &mut String) -> Result<(), ViteError>;
+    fn vite_directive_with_nonce(
+        &self,
+        html: &mut String,
+        nonce: Option<&str>,
+    ) -> Result<(), ViteError>;
+    fn react_directive_with_nonce(
+        &self,
+        html: &mut String,
+        nonce: Option<&str>,
+    ) -> Result<(), ViteError>;
+    fn hmr_directive_with_nonce(
+        &self,
+        html: &mut String,
+        nonce: Option<&str>,
+    ) -> Result<(), ViteError>;
+}
+
+impl ViteRegistryDirectives for ViteRegistry {
+    /// Expands `@vite("build")` directives with the tags of the given build's
+    /// entrypoints, as [`ViteDefaultDirectives::vite_directive`] does.
+    ///
+    /// # Arguments
+    /// * `html`    - A mutable reference to a html string.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` instance if a build isn't registered, or if its mode is
+    /// `Manifest` and there is no Manifest.
+    fn vite_directive(&self, html: &mut String) -> Result<(), ViteError> {
+        self.vite_directive_with_nonce(html, None)
+    }
+
+    /// Does the same as [`ViteRegistryDirectives::vite_directive`], adding the given
+    /// CSP nonce to every generated tag.
+    ///
+    /// # Arguments
+    /// * `html`    - A mutable reference to a html string;
+    /// * `nonce`   - The current request's CSP nonce.
+    fn vite_directive_with_nonce(
+        &self,
+        html: &mut String,
+        nonce: Option<&str>,
+    ) -> Result<(), ViteError> {
+        let regex = NAMED_VITE_DIRECTIVE.get_or_init(|| {
+            Regex::new(r#"([ \t]*)@vite\([ \t]*['"]([^'"]+)['"][ \t]*\)([ \t]*)"#).unwrap()
+        });
+
+        try_replace_all(regex, html, |caps| {
+            let vite = self.try_get(&caps[2])?;
+            let tags_or_scripts = vite.get_resolved_vite_scripts_for(vite.entrypoints(), nonce)?;
+            Ok(format!("{}{}{}", &caps[1], tags_or_scripts, &caps[3]))
+        })
+    }
+
+    /// Replaces `@vite::asset("build", "path/to/asset.file")` directives by the asset's
+    /// URL in the given build. If there is no such an asset at the build's manifest, it
+    /// will be replaced by an empty string.
+    ///
+    /// `@vite::assets` is also valid.
+    ///
+    /// # Arguments
+    /// * `html`    - A mutable reference to a html string.
+    fn assets_url_directive(&self, html: &mut String) -> Result<(), ViteError> {
+        let regex = NAMED_VITE_ASSETS_DIRECTIVE.get_or_init(|| {
+            Regex::new(
+                r#"([ \t]*)@vite::asset[s]?\([ \t]*['"]([^'"]+)['"][ \t]*,[ \t]*['"]?([^'")]*)['"]?[ \t]*\)([ \t]*)"#,
+            )
+            .unwrap()
+        });
+
+        try_replace_all(regex, html, |caps| {
+            let asset_url = self
+                .try_get(&caps[2])?
+                .get_asset_url(&caps[3])
+                .unwrap_or_default();
+            Ok(format!("{}{}{}", &caps[1], asset_url, &caps[4]))
+        })
+    }
+
+    /// Expands `@vite::hmr("build")` directives by the build's HMR client script tag.
+    /// If the build's mode is [`ViteMode::Manifest`], they are replaced by an empty
+    /// string.
+    ///
+    /// # Arguments
+    /// * `html`    - A mutable reference to a html string.
+    fn hmr_directive(&self, html: &mut String) -> Result<(), ViteError> {
+        self.hmr_directive_with_nonce(html, None)
+    }
+
+    /// Does the same as [`ViteRegistryDirectives::hmr_directive`], adding the given
+    /// CSP nonce to the HMR client script tag.
+    ///
+    /// # Arguments
+    /// * `html`    - A mutable reference to a html string;
+    /// * `nonce`   - The current request's CSP nonce.
+    fn hmr_directive_with_nonce(
+        &self,
+        html: &mut String,
+        nonce: Option<&str>,
+    ) -> Result<(), ViteError> {
+        let regex = NAMED_VITE_HMR_DIRECTIVE.get_or_init(|| {
+            Regex::new(r#"([ \t]*)@vite::hmr\([ \t]*['"]([^'"]+)['"][ \t]*\)([ \t]*)"#).unwrap()
+        });
+
+        try_replace_all(regex, html, |caps| {
+            let vite = self.try_get(&caps[2])?;
+            Ok(match vite.mode() {
+                ViteMode::Manifest => "".into(),
+                ViteMode::Development => {
+                    let script = vite.get_hmr_script_with_nonce(nonce);
+                    format!("{}{}{}", &caps[1], script, &caps[3])
+                }
+            })
+        })
+    }
+
+    /// Expands `@vite::react("build")` directives to the [react fast refresh] script
+    /// of builds in [`ViteMode::Development`]. Otherwise, they're replaced by an empty
+    /// string.
+    ///
+    /// # Arguments
+    /// * `html`    - A mutable reference to a html string.
+    ///
+    /// [react fast refresh]: https://vite.dev/guide/backend-integration
+    fn react_directive(&self, html: &mut String) -> Result<(), ViteError> {
+        self.react_directive_with_nonce(html, None)
+    }
+
+    /// Does the same as [`ViteRegistryDirectives::react_directive`], adding the given
+    /// CSP nonce to the inline React refresh preamble.
+    ///
+    /// # Arguments
+    /// * `html`    - A mutable reference to a html string;
+    /// * `nonce`   - The current request's CSP nonce.
+    fn react_directive_with_nonce(
+        &self,
+        html: &mut String,
+        nonce: Option<&str>,
+    ) -> Result<(), ViteError> {
+        let regex = NAMED_VITE_REACT_DIRECTIVE.get_or_init(|| {
+            Regex::new(r#"([ \t]*)@vite::react\([ \t]*['"]([^'"]+)['"][ \t]*\)([ \t]*)"#).unwrap()
+        });
+
+        try_replace_all(regex, html, |caps| {
+            let vite = self.try_get(&caps[2])?;
+            Ok(match vite.mode() {
+                ViteMode::Development => {
+                    let script = vite.get_react_script_with_nonce(nonce);
+                    format!("{}{}{}", &caps[1], script, &caps[3])
+                }
+                ViteMode::Manifest => "".into(),
+            })
+        })
+    }
+}
+
+/// Same as [`Regex::replace_all`], but stops at the first replacement that fails,
+/// leaving `html` untouched.
+fn try_replace_all(
+    regex: &Regex,
+    html: &mut String,
+    replacement: impl Fn(&regex::Captures) -> Result<String, ViteError>,
+) -> Result<(), ViteError> {
+    let mut replaced = String::with_capacity(html.len());
+    let mut last_match = 0;
+
+    for caps in regex.captures_iter(html) {
+        let matched = caps.get(0).unwrap();
+        replaced.push_str(&html[last_match..matched.start()]);
+        replaced.push_str(&replacement(&caps)?);
+        last_match = matched.end();
+    }
+
+    replaced.push_str(&html[last_match..]);
+    *html = replaced;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
-    use crate::features::html_directives::ViteDefaultDirectives;
+    use crate::error::ViteErrorKind;
+    use crate::features::html_directives::{ViteDefaultDirectives, ViteRegistryDirectives};
     use crate::test_utils::NormalizeHtmlStrings;
-    use crate::{Vite, ViteConfig, ViteMode};
+    use crate::ViteError;
+    use crate::{Vite, ViteConfig, ViteMode, ViteRegistry};
 
     async fn get_vites() -> (Vite, Vite) {
-        let mut conf = ViteConfig::default().set_manifest_path("tests/test-manifest.json");
-        conf.entrypoints = Some(vec!["views/foo.js"]);
+        let mut conf = ViteConfig::default()
+            .set_manifest_path("tests/test-manifest.json")
+            .set_entrypoints(["views/foo.js"]);
         conf.force_mode = Some(ViteMode::Development);
         let dev_vite = Vite::new(conf.clone()).await.unwrap();
 
@@ -246,6 +522,71 @@ mod test {
         assert_eq!(manifest_directive, manifest_expected);
     }
 
+    #[tokio::test]
+    async fn test_directives_with_nonce() {
+        let (dev, manifest) = get_vites().await;
+
+        let dev_expected = r#"
+            <script type="module" nonce="r4nd0m">
+                import RefreshRuntime from 'http://localhost:5173/@react-refresh'
+                RefreshRuntime.injectIntoGlobalHook(window)
+                window.$RefreshReg$ = () => {}
+                window.$RefreshSig$ = () => (type) => type
+                window.__vite_plugin_react_preamble_installed__ = true
+            </script>
+            <script type="module" src="http://localhost:5173/views/foo.js" nonce="r4nd0m"></script>
+            <script type="module" src="http://localhost:5173/@vite/client" nonce="r4nd0m"></script>
+            "#;
+
+        let manifest_expected = r#"
+            <link rel="stylesheet" href="/assets/foo-5UjPuW-k.css" nonce="r4nd0m" />
+            <link rel="stylesheet" href="/assets/shared-ChJ_j-JJ.css" nonce="r4nd0m" />
+            <script type="module" src="/assets/foo-BRBmoGS9.js" nonce="r4nd0m"></script>
+            <link rel="modulepreload" href="/assets/shared-B7PI925R.js" nonce="r4nd0m" />
+            "#;
+
+        let mut dev_directive = "@vite::react\n@vite".to_string();
+        let mut manifest_directive = dev_directive.clone();
+
+        dev.react_directive_with_nonce(&mut dev_directive, Some("r4nd0m"));
+        dev.vite_directive_with_nonce(&mut dev_directive, Some("r4nd0m"))
+            .unwrap();
+        assert_eq!(
+            dev_directive.__normalize_html_strings(),
+            dev_expected.__normalize_html_strings()
+        );
+
+        manifest.react_directive_with_nonce(&mut manifest_directive, Some("r4nd0m"));
+        manifest
+            .vite_directive_with_nonce(&mut manifest_directive, Some("r4nd0m"))
+            .unwrap();
+        assert_eq!(
+            manifest_directive.__normalize_html_strings(),
+            manifest_expected.__normalize_html_strings()
+        );
+    }
+
+    #[test]
+    fn test_default_nonce_directives() {
+        struct Upper;
+
+        impl ViteDefaultDirectives for Upper {
+            fn vite_directive(&self, html: &mut String) -> Result<(), ViteError> {
+                *html = html.replace("@vite", "VITE");
+                Ok(())
+            }
+            fn assets_url_directive(&self, _html: &mut String) {}
+            fn react_directive(&self, _html: &mut String) {}
+            fn hmr_directive(&self, _html: &mut String) {}
+        }
+
+        let mut template = "@vite".to_string();
+        Upper
+            .vite_directive_with_nonce(&mut template, Some("r4nd0m"))
+            .unwrap();
+        assert_eq!("VITE", template);
+    }
+
     #[tokio::test]
     async fn test_assets_directive() {
         let (dev, manifest) = get_vites().await;
@@ -259,4 +600,58 @@ mod test {
         manifest.assets_url_directive(&mut manifest_directive);
         assert_eq!(manifest_directive, "/assets/baz-B2H3sXNv.js");
     }
+
+    #[tokio::test]
+    async fn test_registry_directives() {
+        let (dev, manifest) = get_vites().await;
+
+        let mut registry = ViteRegistry::new();
+        registry.insert("admin", dev);
+        registry.insert("storefront", manifest);
+
+        let expected = r#"
+            <head>
+                <script type="module" src="http://localhost:5173/@vite/client"></script>
+
+                <script type="module" src="http://localhost:5173/views/foo.js"></script>
+                <script type="module" src="http://localhost:5173/@vite/client"></script>
+                <link rel="stylesheet" href="/assets/foo-5UjPuW-k.css" />
+                <link rel="stylesheet" href="/assets/shared-ChJ_j-JJ.css" />
+                <script type="module" src="/assets/foo-BRBmoGS9.js"></script>
+                <link rel="modulepreload" href="/assets/shared-B7PI925R.js" />
+                <img src="http://localhost:5173/baz.js" />
+                <img src="/assets/baz-B2H3sXNv.js" />
+            </head>
+            "#;
+
+        let mut template = r#"
+            <head>
+                @vite::hmr("admin")
+                @vite::hmr("storefront")
+                @vite("admin")
+                @vite('storefront')
+                <img src="@vite::asset("admin", "baz.js")" />
+                <img src="@vite::asset('storefront', 'baz.js')" />
+            </head>
+            "#
+        .to_string();
+
+        registry.hmr_directive(&mut template).unwrap();
+        registry.vite_directive(&mut template).unwrap();
+        registry.assets_url_directive(&mut template).unwrap();
+        assert_eq!(
+            template.__normalize_html_strings(),
+            expected.__normalize_html_strings()
+        );
+
+        let mut template = "@vite(\"admin\")\n@vite(\"blog\")".to_string();
+        let error = registry.vite_directive(&mut template).unwrap_err();
+        assert_eq!(
+            &ViteErrorKind::UnknownBuild {
+                name: "blog".into()
+            },
+            error.kind()
+        );
+        assert_eq!("@vite(\"admin\")\n@vite(\"blog\")", template);
+    }
 }
@@ -0,0 +1,555 @@
+use std::error::Error;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::{Message, WebSocket};
+
+use crate::utils::{split_url, DevServerStream, DevServerTls};
+use crate::Vite;
+
+/// How long the listener thread blocks reading the socket before checking whether
+/// it must stop.
+const READ_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The timeout for connecting to the dev-server if the heart beat has none.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A message broadcast by the Vite dev-server over its HMR WebSocket.
+///
+/// See Vite's [HMR payload] types for the meaning of each field.
+///
+/// [HMR payload]: https://github.com/vitejs/vite/blob/main/packages/vite/types/hmrPayload.d.ts
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum HmrEvent {
+    /// The listener has (re)connected to the dev-server.
+    Connected,
+    /// Modules have changed and are hot-updated by the browser.
+    Update { updates: Vec<HmrUpdate> },
+    /// The browser reloads the page, e.g. because an HTML file or a module that
+    /// can't be hot-updated has changed.
+    #[serde(rename_all = "camelCase")]
+    FullReload {
+        path: Option<String>,
+        triggered_by: Option<String>,
+    },
+    /// Modules are no longer imported.
+    Prune { paths: Vec<String> },
+    /// A module failed to build, which the browser shows in the error overlay.
+    Error { err: HmrError },
+    /// An event sent by a plugin, e.g. through `server.ws.send("my:event", data)`.
+    Custom {
+        event: String,
+        #[serde(default)]
+        data: serde_json::Value,
+    },
+}
+
+/// A module hot-updated by an [`HmrEvent::Update`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HmrUpdate {
+    #[serde(rename = "type")]
+    pub kind: HmrUpdateKind,
+    /// The root-relative path of the updated module, e.g. `/src/App.tsx`.
+    pub path: String,
+    /// The path of the module accepting the update.
+    pub accepted_path: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HmrUpdateKind {
+    JsUpdate,
+    CssUpdate,
+}
+
+/// A build error reported by an [`HmrEvent::Error`], or a backend error shown in
+/// Vite's error overlay by [`Vite::show_error_overlay`].
+///
+/// [`Vite::show_error_overlay`]: crate::Vite::show_error_overlay
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HmrError {
+    pub message: String,
+    #[serde(default)]
+    pub stack: String,
+    /// The module that failed to build.
+    pub id: Option<String>,
+    /// A code frame pointing at the error.
+    pub frame: Option<String>,
+    /// The plugin that raised the error.
+    pub plugin: Option<String>,
+    pub loc: Option<HmrErrorLocation>,
+}
+
+impl HmrError {
+    pub fn new(message: impl Into<String>) -> Self {
+        HmrError {
+            message: message.into(),
+            stack: String::new(),
+            id: None,
+            frame: None,
+            plugin: Some("vite-rust".to_string()),
+            loc: None,
+        }
+    }
+
+    /// Creates an error from a Rust error, whose sources are listed as its stack.
+    pub fn from_error(err: &(dyn Error + 'static)) -> Self {
+        let mut stack = Vec::new();
+        let mut source = err.source();
+        while let Some(err) = source {
+            stack.push(format!("Caused by: {err}"));
+            source = err.source();
+        }
+
+        HmrError {
+            stack: stack.join("\n"),
+            ..HmrError::new(err.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HmrErrorLocation {
+    pub file: Option<String>,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// Handle to a background thread listening to the Vite dev-server's HMR WebSocket,
+/// receiving its messages as [`HmrEvent`]s. Useful for invalidating caches or
+/// logging build errors whenever the frontend changes.
+///
+/// The thread reconnects whenever the dev-server restarts, sending a new
+/// [`HmrEvent::Connected`], and stops as soon as this handle is dropped. The
+/// dev-server URL is looked up again on each reconnection, so that the listener
+/// follows the dev-server when it moves (see [`Vite::monitor_dev_server`]).
+#[derive(Debug)]
+pub struct HmrListener {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    events: Receiver<HmrEvent>,
+}
+
+impl HmrListener {
+    pub(crate) fn spawn(vite: Vite, retry_interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let should_stop = Arc::clone(&stop);
+        let (sender, events) = mpsc::channel();
+        let connect_timeout = vite.heart_beat_timeout.unwrap_or(CONNECT_TIMEOUT);
+
+        let thread = thread::spawn(move || loop {
+            let url = vite.dev_server_host();
+            match connect(&url, connect_timeout, &vite.dev_server_tls) {
+                Ok(socket) => {
+                    log::debug!("Listening to the Vite HMR WebSocket at {url}.");
+                    if !listen(socket, &sender, &should_stop) {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    log::debug!("Failed to connect to the Vite HMR WebSocket at {url}: {err}")
+                }
+            }
+
+            if should_stop.load(Ordering::Acquire) {
+                break;
+            }
+
+            thread::park_timeout(retry_interval);
+
+            if should_stop.load(Ordering::Acquire) {
+                break;
+            }
+        });
+
+        HmrListener {
+            stop,
+            thread: Some(thread),
+            events,
+        }
+    }
+
+    /// Blocks until the next event is received.
+    pub fn recv(&self) -> Option<HmrEvent> {
+        self.events.recv().ok()
+    }
+
+    /// Returns the next event if one has already been received.
+    pub fn try_recv(&self) -> Option<HmrEvent> {
+        self.events.try_recv().ok()
+    }
+
+    /// Blocks until the next event is received, or `timeout` is elapsed.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<HmrEvent> {
+        self.events.recv_timeout(timeout).ok()
+    }
+
+    /// Returns a blocking iterator over the events, as they are received.
+    pub fn iter(&self) -> impl Iterator<Item = HmrEvent> + '_ {
+        self.events.iter()
+    }
+}
+
+impl Drop for HmrListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+fn connect(
+    url: &str,
+    timeout: Duration,
+    tls: &DevServerTls,
+) -> io::Result<WebSocket<DevServerStream>> {
+    let (scheme, authority, _, path) = split_url(url)?;
+    let ws_scheme = match scheme {
+        "https" => "wss",
+        _ => "ws",
+    };
+
+    let mut request = format!("{ws_scheme}://{authority}{path}")
+        .into_client_request()
+        .map_err(io::Error::other)?;
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        tungstenite::http::HeaderValue::from_static("vite-hmr"),
+    );
+
+    let stream = DevServerStream::connect(url, timeout, tls)?;
+    let (socket, _) = tungstenite::client(request, stream).map_err(|err| match err {
+        tungstenite::HandshakeError::Failure(err) => io::Error::other(err),
+        tungstenite::HandshakeError::Interrupted(_) => {
+            io::Error::new(io::ErrorKind::TimedOut, "the WebSocket handshake timed out")
+        }
+    })?;
+
+    socket
+        .get_ref()
+        .tcp()
+        .set_read_timeout(Some(READ_INTERVAL))?;
+    Ok(socket)
+}
+
+/// Sends a custom event to the dev-server over a short-lived HMR WebSocket connection.
+pub(crate) fn send_custom_event(
+    url: &str,
+    timeout: Option<Duration>,
+    tls: &DevServerTls,
+    event: &str,
+    data: serde_json::Value,
+) -> io::Result<()> {
+    let mut socket = connect(url, timeout.unwrap_or(CONNECT_TIMEOUT), tls)?;
+    let message = serde_json::json!({ "type": "custom", "event": event, "data": data });
+
+    socket
+        .send(Message::text(message.to_string()))
+        .map_err(io::Error::other)?;
+    let _ = socket.close(None);
+    let _ = socket.flush();
+
+    Ok(())
+}
+
+/// Forwards the socket's messages to `sender` until the connection is lost, returning
+/// whether the listener should reconnect.
+fn listen(
+    mut socket: WebSocket<DevServerStream>,
+    sender: &Sender<HmrEvent>,
+    should_stop: &AtomicBool,
+) -> bool {
+    loop {
+        if should_stop.load(Ordering::Acquire) {
+            let _ = socket.close(None);
+            return false;
+        }
+
+        let text = match socket.read() {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => return true,
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(err) => {
+                log::debug!("Lost the connection to the Vite HMR WebSocket: {err}");
+                return true;
+            }
+        };
+
+        match serde_json::from_str::<HmrEvent>(&text) {
+            Ok(event) => {
+                if sender.send(event).is_err() {
+                    return false;
+                }
+            }
+            Err(err) => log::debug!("Ignoring the Vite HMR message {text}: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use std::{fs, io, thread};
+
+    use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+    use tungstenite::Message;
+
+    use super::{HmrError, HmrEvent, HmrUpdate, HmrUpdateKind};
+    use crate::error::{ViteError, ViteErrorKind};
+    use crate::test_utils::TempFile;
+    use crate::{Vite, ViteConfig, ViteMode};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Completes the handshake of a Vite client the way the dev-server does.
+    #[allow(clippy::result_large_err)]
+    fn accept_vite_hmr(
+        request: &Request,
+        mut response: Response,
+    ) -> Result<Response, ErrorResponse> {
+        assert_eq!("vite-hmr", request.headers()["sec-websocket-protocol"]);
+        response
+            .headers_mut()
+            .insert("sec-websocket-protocol", "vite-hmr".parse().unwrap());
+        Ok(response)
+    }
+
+    #[test]
+    fn test_deserialize_events() {
+        let event: HmrEvent = serde_json::from_str(
+            r#"{"type":"update","updates":[{"type":"css-update","path":"/src/app.css","acceptedPath":"/src/app.css","timestamp":1712345678901,"explicitImportRequired":false}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            HmrEvent::Update {
+                updates: vec![HmrUpdate {
+                    kind: HmrUpdateKind::CssUpdate,
+                    path: "/src/app.css".into(),
+                    accepted_path: "/src/app.css".into(),
+                    timestamp: 1712345678901,
+                }]
+            },
+            event
+        );
+
+        let event: HmrEvent =
+            serde_json::from_str(r#"{"type":"full-reload","triggeredBy":"/app/index.html"}"#)
+                .unwrap();
+        assert_eq!(
+            HmrEvent::FullReload {
+                path: None,
+                triggered_by: Some("/app/index.html".into())
+            },
+            event
+        );
+
+        let event: HmrEvent = serde_json::from_str(
+            r#"{"type":"error","err":{"message":"Unexpected token","stack":"","id":"/src/main.ts","plugin":"vite:esbuild","loc":{"file":"/src/main.ts","line":3,"column":7}}}"#,
+        )
+        .unwrap();
+        let HmrEvent::Error { err } = event else {
+            panic!("expected an error event, got {event:?}");
+        };
+        assert_eq!("Unexpected token", err.message);
+        assert_eq!(Some("vite:esbuild"), err.plugin.as_deref());
+        assert_eq!(3, err.loc.unwrap().line);
+
+        assert!(serde_json::from_str::<HmrEvent>(r#"{"type":"ping"}"#).is_err());
+    }
+
+    #[test]
+    fn test_listen_hmr() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept_hdr(stream, accept_vite_hmr).unwrap();
+
+            for message in [
+                r#"{"type":"connected"}"#,
+                r#"{"type":"ping"}"#,
+                r#"{"type":"prune","paths":["/src/old.ts"]}"#,
+                r#"{"type":"custom","event":"app:saved","data":{"id":1}}"#,
+            ] {
+                socket.send(Message::text(message)).unwrap();
+            }
+
+            // waits for the listener to close the connection
+            while socket.read().is_ok() {}
+        });
+
+        let vite = Vite::new_blocking(
+            ViteConfig::default()
+                .set_entrypoints(["src/main.ts"])
+                .set_server_host(host)
+                .set_force_mode(ViteMode::Development),
+        )
+        .unwrap();
+
+        let hmr = vite.listen_hmr(Duration::from_millis(100));
+
+        assert_eq!(Some(HmrEvent::Connected), hmr.recv_timeout(TIMEOUT));
+        assert_eq!(
+            Some(HmrEvent::Prune {
+                paths: vec!["/src/old.ts".into()]
+            }),
+            hmr.recv_timeout(TIMEOUT)
+        );
+        assert_eq!(
+            Some(HmrEvent::Custom {
+                event: "app:saved".into(),
+                data: serde_json::json!({ "id": 1 })
+            }),
+            hmr.recv_timeout(TIMEOUT)
+        );
+        assert_eq!(None, hmr.try_recv());
+
+        drop(hmr);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_listen_hmr_follows_hot_file() {
+        let first_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let first_host = format!("http://{}", first_listener.local_addr().unwrap());
+        let second_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let second_host = format!("http://{}", second_listener.local_addr().unwrap());
+
+        let (moved, has_moved) = mpsc::channel::<()>();
+        let first_server = thread::spawn(move || {
+            let (stream, _) = first_listener.accept().unwrap();
+            let mut socket = tungstenite::accept_hdr(stream, accept_vite_hmr).unwrap();
+            socket
+                .send(Message::text(r#"{"type":"connected"}"#))
+                .unwrap();
+
+            // the dev-server restarts on another port
+            has_moved.recv().unwrap();
+            socket.close(None).unwrap();
+            while socket.read().is_ok() {}
+        });
+        let second_server = thread::spawn(move || {
+            let (stream, _) = second_listener.accept().unwrap();
+            let mut socket = tungstenite::accept_hdr(stream, accept_vite_hmr).unwrap();
+            socket
+                .send(Message::text(
+                    r#"{"type":"custom","event":"app:moved","data":null}"#,
+                ))
+                .unwrap();
+            while socket.read().is_ok() {}
+        });
+
+        let hot_file = TempFile::new("hmr-hot-file");
+        fs::write(&hot_file, &first_host).unwrap();
+        let vite = Vite::new_blocking(
+            ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+                .set_hot_file_path(hot_file.to_str()),
+        )
+        .unwrap();
+
+        let _monitor = vite.monitor_dev_server(Duration::from_millis(10));
+        let hmr = vite.listen_hmr(Duration::from_millis(10));
+        assert_eq!(Some(HmrEvent::Connected), hmr.recv_timeout(TIMEOUT));
+
+        fs::write(&hot_file, &second_host).unwrap();
+        for _ in 0..500 {
+            if vite.get_dev_server_url() == second_host {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(second_host, vite.get_dev_server_url());
+        moved.send(()).unwrap();
+
+        assert_eq!(
+            Some(HmrEvent::Custom {
+                event: "app:moved".into(),
+                data: serde_json::Value::Null
+            }),
+            hmr.recv_timeout(TIMEOUT)
+        );
+
+        drop(hmr);
+        first_server.join().unwrap();
+        second_server.join().unwrap();
+    }
+
+    #[test]
+    fn test_show_error_overlay() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept_hdr(stream, accept_vite_hmr).unwrap();
+
+            let message = socket.read().unwrap();
+            serde_json::from_str::<serde_json::Value>(message.to_text().unwrap()).unwrap()
+        });
+
+        let vite = Vite::new_blocking(
+            ViteConfig::default()
+                .set_entrypoints(["src/main.ts"])
+                .set_server_host(host)
+                .set_force_mode(ViteMode::Development),
+        )
+        .unwrap();
+
+        let source = io::Error::other("no such template");
+        let err = ViteError::with_source(
+            "Failed to render users/show.html",
+            ViteErrorKind::Io,
+            source,
+        );
+        vite.show_error_overlay(&HmrError::from_error(&err))
+            .unwrap();
+
+        let message = server.join().unwrap();
+        assert_eq!("custom", message["type"]);
+        assert_eq!("vite-rust:error", message["event"]);
+        assert_eq!(
+            "Failed to render users/show.html",
+            message["data"]["message"]
+        );
+        assert_eq!("Caused by: no such template", message["data"]["stack"]);
+        assert_eq!("vite-rust", message["data"]["plugin"]);
+
+        let error = Vite::new_blocking(
+            ViteConfig::default()
+                .set_entrypoints(["src/main.ts"])
+                .set_server_host("http://127.0.0.1:1")
+                .set_force_mode(ViteMode::Development),
+        )
+        .unwrap()
+        .trigger_full_reload()
+        .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ViteErrorKind::DevServerUnreachable { .. }
+        ));
+    }
+}
@@ -2,18 +2,42 @@ mod asset;
 mod chunk;
 mod config;
 pub mod error;
+#[cfg(feature = "hmr")]
+mod hmr;
 mod manifest;
+mod monitor;
+#[cfg(feature = "proxy")]
+mod proxy;
+mod registry;
+mod tag;
 mod vite;
 
 #[cfg(test)]
 mod test_utils;
 
 const CLIENT_SCRIPT_PATH: &str = r#"@vite/client"#;
+/// The custom HMR events relayed to the browser by `vite-rs-plugin`.
+const BACKEND_RELOAD_EVENT: &str = "vite-rust:full-reload";
+const BACKEND_ERROR_EVENT: &str = "vite-rust:error";
 
 pub mod features;
 pub mod utils;
 
+pub use config::ConfigProblem;
+pub use config::ProductionPredicate;
 pub use config::ViteConfig;
 pub use config::ViteMode;
+pub use config::{
+    DEFAULT_HEART_BEAT_DEADLINE, DEFAULT_HEART_BEAT_TIMEOUT, DEFAULT_PRODUCTION_ENV_VALUES,
+    DEFAULT_PRODUCTION_ENV_VARS, DEFAULT_STYLE_SHEET_EXTENSIONS,
+};
 pub use error::ViteError;
+#[cfg(feature = "hmr")]
+pub use hmr::{HmrError, HmrErrorLocation, HmrEvent, HmrListener, HmrUpdate, HmrUpdateKind};
+pub use manifest::ManifestWatcher;
+pub use monitor::DevServerMonitor;
+#[cfg(feature = "proxy")]
+pub use proxy::{DevServerProxy, HmrTunnel, DEFAULT_PROXIED_PATHS};
+pub use registry::ViteRegistry;
+pub use tag::{Tag, TagKind};
 pub use vite::Vite;
@@ -1,27 +1,63 @@
+use base64::prelude::{Engine, BASE64_STANDARD};
 use md5::{Digest, Md5};
-use serde::Deserialize;
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
+use sha2::Sha384;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
 use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
-use crate::asset::Asset;
+use crate::asset::{is_style_sheet, legacy_tags, Asset};
 use crate::chunk::Chunk;
 use crate::error::{ViteError, ViteErrorKind};
-use crate::vite::Entrypoints;
+use crate::tag::Tag;
+
+/// Key of the polyfills chunk generated by `@vitejs/plugin-legacy`.
+const LEGACY_POLYFILLS_KEY: &str = "vite/legacy-polyfills-legacy";
+
+/// Settings from [`ViteConfig`] that change which tags are generated from the
+/// manifest, and how.
+///
+/// [`ViteConfig`]: crate::ViteConfig
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TagsOptions {
+    /// Whether to add `integrity` attributes to the tags.
+    pub(crate) subresource_integrity: bool,
+    /// How many levels of dynamic imports to follow when emitting prefetch hints,
+    /// if any.
+    pub(crate) prefetch_depth: Option<u8>,
+    /// Extensions of the entrypoints rendered as stylesheets.
+    pub(crate) style_sheet_extensions: Arc<[String]>,
+}
 
-#[derive(Deserialize, Debug)]
+#[derive(Debug)]
 pub(crate) struct Manifest {
     manifest: HashMap<String, Chunk>,
     hash: String,
+    /// The directory where the bundled files live (Vite's `build.outDir`).
+    out_dir: PathBuf,
+    /// Subresource Integrity hashes by bundled file, either taken from the
+    /// chunks' `integrity` field or lazily computed from the file on disk.
+    integrity_cache: Mutex<HashMap<String, String>>,
 }
 
 impl Manifest {
     pub fn new(path: &str) -> Result<Self, ViteError> {
+        let manifest_content = Manifest::read(path)?;
+        Manifest::parse(path, &manifest_content)
+    }
+
+    fn read(path: &str) -> Result<String, ViteError> {
         let mut file = match File::open(path) {
             Err(err) => {
-                return Err(ViteError::new(
+                return Err(ViteError::from_io(
                     format!("Failed to open manifest at {}: {}", path, err),
-                    ViteErrorKind::Manifest,
+                    path,
+                    err,
                 ));
             }
             Ok(file) => file,
@@ -29,30 +65,79 @@ impl Manifest {
 
         let mut manifest_content = String::new();
         if let Err(err) = file.read_to_string(&mut manifest_content) {
-            return Err(ViteError::new(
+            return Err(ViteError::from_io(
                 format!("Failed to read manifest.json content: {}", err),
-                ViteErrorKind::Manifest,
+                path,
+                err,
             ));
         };
 
-        let hash = match Manifest::get_hash_from_manifest(&manifest_content) {
-            Ok(hash) => hash,
+        Ok(manifest_content)
+    }
+
+    fn parse(path: &str, manifest_content: &str) -> Result<Self, ViteError> {
+        let hash = Manifest::hash(manifest_content)?;
+        Manifest::parse_with_hash(path, manifest_content, hash)
+    }
+
+    fn hash(manifest_content: &str) -> Result<String, ViteError> {
+        Manifest::get_hash_from_manifest(manifest_content).map_err(|err| {
+            ViteError::with_source(
+                format!("Failed to generate hash for manifest: {err}"),
+                ViteErrorKind::Io,
+                err,
+            )
+        })
+    }
+
+    fn parse_with_hash(
+        path: &str,
+        manifest_content: &str,
+        hash: String,
+    ) -> Result<Self, ViteError> {
+        let manifest: HashMap<String, Chunk> = match serde_json::from_str(manifest_content) {
             Err(err) => {
-                return Err(ViteError::new(
-                    format!("Failed to generate hash for manifest: {err}"),
-                    ViteErrorKind::Manifest,
+                return Err(ViteError::with_source(
+                    format!("Failed to parse manifest json: {}", err),
+                    ViteErrorKind::JsonParse {
+                        line: err.line(),
+                        column: err.column(),
+                    },
+                    err,
                 ))
             }
+            Ok(manifest) => manifest,
         };
 
-        let manifest = serde_json::from_str(&manifest_content);
+        let integrity_cache = manifest
+            .values()
+            .filter_map(|chunk| {
+                let integrity = chunk.integrity.clone()?;
+                Some((chunk.file.clone(), integrity))
+            })
+            .collect();
+
+        Ok(Manifest {
+            manifest,
+            hash,
+            out_dir: Manifest::resolve_out_dir(path),
+            integrity_cache: Mutex::new(integrity_cache),
+        })
+    }
 
-        match manifest {
-            Err(err) => Err(ViteError::new(
-                format!("Failed to parse manifest json: {}", err),
-                ViteErrorKind::Manifest,
-            )),
-            Ok(manifest) => Ok(Manifest { manifest, hash }),
+    /// Vite >= 5 writes the manifest to `outDir/.vite/manifest.json`, while older
+    /// versions write it to `outDir/manifest.json`.
+    fn resolve_out_dir(manifest_path: &str) -> PathBuf {
+        let manifest_dir = Path::new(manifest_path)
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+
+        match manifest_dir.file_name() {
+            Some(dir_name) if dir_name == ".vite" => manifest_dir
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .to_path_buf(),
+            _ => manifest_dir.to_path_buf(),
         }
     }
 
@@ -75,75 +160,341 @@ impl Manifest {
         &self.hash
     }
 
-    pub fn generate_html_tags(
+    /// Returns the Subresource Integrity hash of the given bundled file.
+    ///
+    /// The `integrity` field from the manifest is preferred. If it is absent,
+    /// a `sha384` hash is computed from the file inside the output directory.
+    pub(crate) fn get_integrity(&self, file: &str) -> Option<String> {
+        if let Some(integrity) = self.cached_integrity(file) {
+            return Some(integrity);
+        }
+
+        // computed without holding the lock, and only cached once the file is found,
+        // so that a file missing for a while (e.g. during a deploy) is retried later
+        match fs::read(self.out_dir.join(file)) {
+            Ok(content) => {
+                let integrity =
+                    format!("sha384-{}", BASE64_STANDARD.encode(Sha384::digest(content)));
+                self.integrity_cache
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .insert(file.to_string(), integrity.clone());
+                Some(integrity)
+            }
+            Err(err) => {
+                log::warn!(r#"Failed to compute the integrity hash of "{file}": {err}."#);
+                None
+            }
+        }
+    }
+
+    fn cached_integrity(&self, file: &str) -> Option<String> {
+        self.integrity_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(file)
+            .cloned()
+    }
+
+    /// Generates the structured [`Tag`]s for the given entrypoints' assets.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if a chunk imports another one missing from the manifest.
+    pub fn generate_tags<E: AsRef<str>>(
         &self,
-        entrypoints: &Entrypoints,
-        prefix: Option<&'static str>,
-        app_url: &'static str,
-    ) -> String {
+        entrypoints: &[E],
+        prefix: Option<&str>,
+        app_url: &str,
+        options: &TagsOptions,
+        nonce: Option<&str>,
+    ) -> Result<Vec<Tag>, ViteError> {
         if self.manifest.is_empty() {
-            log::error!("Manifest is empty. Empty string being returned from `Manifest::generate_html_tags`.");
-            return "".into();
+            log::error!(
+                "Manifest is empty. No tags being returned from `Manifest::generate_tags`."
+            );
+            return Ok(Vec::new());
         }
 
-        let mut discovered_assets = HashSet::<Asset>::new();
+        let discovered_assets = self.discover_assets(entrypoints, prefix, app_url, options)?;
+
+        let mut tags = discovered_assets
+            .into_iter()
+            .filter_map(|(asset, file)| {
+                let integrity = match options.subresource_integrity && asset.supports_integrity() {
+                    true => self.get_integrity(file),
+                    false => None,
+                };
+
+                asset.into_tag(integrity.as_deref(), nonce)
+            })
+            .collect::<Vec<Tag>>();
+
+        tags.extend(self.generate_legacy_tags(entrypoints, prefix, app_url, nonce));
+
+        Ok(tags)
+    }
+
+    /// Generates [RFC 8288] `Link` header values for the same assets discovered by
+    /// [`Manifest::generate_tags`], e.g. `</assets/foo.css>; rel=preload; as=style`.
+    ///
+    /// [RFC 8288]: https://www.rfc-editor.org/rfc/rfc8288
+    pub fn generate_link_headers<E: AsRef<str>>(
+        &self,
+        entrypoints: &[E],
+        prefix: Option<&str>,
+        app_url: &str,
+        options: &TagsOptions,
+    ) -> Result<Vec<String>, ViteError> {
+        Ok(self
+            .discover_assets(entrypoints, prefix, app_url, options)?
+            .into_iter()
+            .filter_map(|(asset, file)| {
+                let crossorigin = options.subresource_integrity
+                    && asset.supports_integrity()
+                    && self.get_integrity(file).is_some();
+
+                asset.into_link_header(crossorigin)
+            })
+            .collect())
+    }
+
+    /// Walks the chunks graph from the given entrypoints, mapping every asset they
+    /// need to the bundled file it was resolved from.
+    ///
+    /// The map keeps the assets in the following order:
+    /// stylesheets > entries > preloads > prefetches
+    fn discover_assets<'a, E: AsRef<str>>(
+        &'a self,
+        entrypoints: &[E],
+        prefix: Option<&str>,
+        app_url: &str,
+        options: &TagsOptions,
+    ) -> Result<BTreeMap<Asset, &'a str>, ViteError> {
+        let mut discovered_assets = BTreeMap::<Asset, &str>::new();
+        let mut visited_chunks = HashSet::<&str>::new();
 
         for entry in entrypoints {
-            let entry_chunk = match self.manifest.get(entry.as_ref()) {
+            let entry = entry.as_ref();
+            let (entry_key, entry_chunk) = match self.manifest.get_key_value(entry) {
                 None => {
                     log::error!(r#"Skipping invalid or unexisting entry "{entry}"."#);
                     continue;
                 }
-                Some(chunk) => chunk,
+                Some(entry) => entry,
             };
 
-            let entry_as_asset = if entry.ends_with(".css") {
+            let entry_as_asset = if is_style_sheet(entry, &options.style_sheet_extensions)
+                || is_style_sheet(&entry_chunk.file, &[".css"])
+            {
                 Asset::style_sheet(entry_chunk.file.clone(), prefix, app_url)
             } else {
                 Asset::entry_point(entry_chunk.file.clone(), prefix, app_url)
             };
 
-            if !discovered_assets.contains(&entry_as_asset) {
-                discovered_assets.insert(entry_as_asset);
+            if let Entry::Vacant(slot) = discovered_assets.entry(entry_as_asset) {
+                slot.insert(&entry_chunk.file);
+                visited_chunks.insert(entry_key);
                 self.iterate_over_chunk_assets(
                     &mut discovered_assets,
+                    &mut visited_chunks,
                     entry_chunk,
                     prefix,
                     app_url,
-                );
+                )?;
             }
         }
 
-        let mut assets = discovered_assets.into_iter().collect::<Vec<Asset>>();
-        // Puts the assets in the following order: stylesheets > entries > preloads
-        assets.sort();
+        if let Some(depth) = options.prefetch_depth {
+            let eager_files = discovered_assets
+                .values()
+                .copied()
+                .collect::<HashSet<&str>>();
+            let mut visited_depths = HashMap::<&str, u8>::new();
+
+            for entry in entrypoints {
+                if let Some(entry_chunk) = self.manifest.get(entry.as_ref()) {
+                    self.iterate_over_dynamic_imports(
+                        &mut discovered_assets,
+                        &eager_files,
+                        &mut visited_depths,
+                        entry_chunk,
+                        depth,
+                        prefix,
+                        app_url,
+                    );
+                }
+            }
+        }
 
-        assets
-            .into_iter()
-            .map(|asset| asset.into_html())
-            .collect::<Vec<String>>()
-            .join("\n")
+        Ok(discovered_assets)
     }
 
-    fn iterate_over_chunk_assets(
+    /// Generates the `nomodule` scripts for the entrypoints' legacy twins, if the
+    /// build includes `@vitejs/plugin-legacy` chunks. Otherwise, nothing is generated.
+    fn generate_legacy_tags<E: AsRef<str>>(
         &self,
-        set: &mut HashSet<Asset>,
-        chunk: &Chunk,
-        prefix: Option<&'static str>,
-        app_url: &'static str,
+        entrypoints: &[E],
+        prefix: Option<&str>,
+        app_url: &str,
+        nonce: Option<&str>,
+    ) -> Vec<Tag> {
+        let polyfills = match self.manifest.get(LEGACY_POLYFILLS_KEY) {
+            None => return Vec::new(),
+            Some(chunk) => chunk,
+        };
+
+        let legacy_entries = entrypoints
+            .iter()
+            .filter_map(|entry| self.manifest.get(&legacy_key(entry.as_ref())))
+            .map(|chunk| Asset::resolve_asset_path(chunk.file.clone(), prefix, app_url))
+            .collect::<Vec<String>>();
+
+        if legacy_entries.is_empty() {
+            return Vec::new();
+        }
+
+        let polyfills = Asset::resolve_asset_path(polyfills.file.clone(), prefix, app_url);
+        legacy_tags(polyfills, legacy_entries, nonce)
+    }
+
+    /// Whether the chunk under the given key belongs to the legacy bundle generated
+    /// by `@vitejs/plugin-legacy`.
+    fn is_legacy_chunk(&self, key: &str, chunk: &Chunk) -> bool {
+        if chunk.is_legacy_entry {
+            return true;
+        }
+
+        if !self.manifest.contains_key(LEGACY_POLYFILLS_KEY) {
+            return false;
+        }
+
+        let file_name = key.rsplit('/').next().unwrap_or(key);
+        let file_stem = file_name
+            .rsplit_once('.')
+            .map_or(file_name, |(stem, _)| stem);
+
+        file_stem.ends_with("-legacy")
+    }
+
+    /// Adds the chunk's stylesheets and assets, then walks its static imports
+    /// transitively, adding a preload for each of them. Every chunk is visited once,
+    /// so import cycles are safe.
+    fn iterate_over_chunk_assets<'a>(
+        &'a self,
+        map: &mut BTreeMap<Asset, &'a str>,
+        visited: &mut HashSet<&'a str>,
+        chunk: &'a Chunk,
+        prefix: Option<&str>,
+        app_url: &str,
+    ) -> Result<(), ViteError> {
+        for (asset, file) in chunk.assets_iter(prefix, app_url) {
+            map.entry(asset).or_insert(file);
+        }
+
+        for import in chunk.imports.iter() {
+            if !visited.insert(import) {
+                continue;
+            }
+
+            let import_chunk = self.manifest.get(import).ok_or_else(|| {
+                ViteError::new(
+                    format!(
+                        r#"Chunk "{}" imports "{import}", which is missing from the manifest."#,
+                        chunk.file
+                    ),
+                    ViteErrorKind::Manifest,
+                )
+            })?;
+
+            map.entry(Asset::pre_load(import_chunk.file.clone(), prefix, app_url))
+                .or_insert(&import_chunk.file);
+
+            self.iterate_over_chunk_assets(map, visited, import_chunk, prefix, app_url)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds prefetch hints for the chunks lazily imported by the given one, following
+    /// up to `depth` levels of dynamic imports. Every chunk is only walked again if it
+    /// is reached with more levels left than before, so cycles and shared chunks are
+    /// walked once.
+    #[allow(clippy::too_many_arguments)]
+    fn iterate_over_dynamic_imports<'a>(
+        &'a self,
+        map: &mut BTreeMap<Asset, &'a str>,
+        eager_files: &HashSet<&str>,
+        visited_depths: &mut HashMap<&'a str, u8>,
+        chunk: &'a Chunk,
+        depth: u8,
+        prefix: Option<&str>,
+        app_url: &str,
     ) {
-        for asset in chunk.assets_iter(prefix, app_url) {
-            if !set.contains(&asset) {
-                set.insert(asset);
+        if depth == 0 {
+            return;
+        }
+
+        for import in chunk.dynamic_imports.iter() {
+            let import_chunk = match self.manifest.get(import) {
+                None => {
+                    log::warn!(r#"Skipping unexisting dynamic import "{import}"."#);
+                    continue;
+                }
+                Some(chunk) => chunk,
+            };
+
+            if visited_depths
+                .get(import.as_str())
+                .is_some_and(|visited_depth| *visited_depth >= depth)
+            {
+                continue;
             }
+            visited_depths.insert(import, depth);
+
+            self.prefetch_chunk(map, eager_files, import_chunk, prefix, app_url);
+            self.iterate_over_dynamic_imports(
+                map,
+                eager_files,
+                visited_depths,
+                import_chunk,
+                depth - 1,
+                prefix,
+                app_url,
+            );
         }
+    }
 
-        if chunk.is_entry {
-            chunk.imports.iter().for_each(|import| {
-                let import_chunk = &self.manifest[import];
-                set.insert(Asset::pre_load(import_chunk.file.clone(), prefix, app_url));
-                self.iterate_over_chunk_assets(set, import_chunk, prefix, app_url);
-            });
+    /// Adds prefetch hints for the chunk's file, its stylesheets and its static
+    /// imports', skipping the files that are already eagerly loaded.
+    fn prefetch_chunk<'a>(
+        &'a self,
+        map: &mut BTreeMap<Asset, &'a str>,
+        eager_files: &HashSet<&str>,
+        chunk: &'a Chunk,
+        prefix: Option<&str>,
+        app_url: &str,
+    ) {
+        if eager_files.contains(chunk.file.as_str()) {
+            return;
+        }
+
+        match map.entry(Asset::pre_fetch(chunk.file.clone(), prefix, app_url)) {
+            Entry::Vacant(slot) => slot.insert(&chunk.file),
+            // already visited
+            Entry::Occupied(_) => return,
+        };
+
+        for css in chunk.css.iter() {
+            if !eager_files.contains(css.as_str()) {
+                map.entry(Asset::pre_fetch(css.clone(), prefix, app_url))
+                    .or_insert(css);
+            }
+        }
+
+        for import in chunk.imports.iter() {
+            if let Some(import_chunk) = self.manifest.get(import) {
+                self.prefetch_chunk(map, eager_files, import_chunk, prefix, app_url);
+            }
         }
     }
 
@@ -152,7 +503,7 @@ impl Manifest {
         let mut entries = Vec::new();
 
         for (key, chunk) in self.manifest.iter() {
-            if chunk.is_entry {
+            if chunk.is_entry && !self.is_legacy_chunk(key, chunk) {
                 entries.push(key.as_str());
             }
         }
@@ -160,6 +511,10 @@ impl Manifest {
         entries
     }
 
+    pub(crate) fn has_chunk(&self, key: &str) -> bool {
+        self.manifest.contains_key(key)
+    }
+
     pub(crate) fn get_asset_url<'a>(
         &'a self,
         asset: &'a str,
@@ -173,11 +528,116 @@ impl Manifest {
     }
 }
 
+/// Returns the manifest key of the given entry's legacy twin, named as Vite does
+/// for `@vitejs/plugin-legacy` builds. E.g., `src/main.js` becomes `src/main-legacy.js`.
+fn legacy_key(entry: &str) -> String {
+    let file_name_start = entry.rfind('/').map_or(0, |index| index + 1);
+
+    match entry[file_name_start..].rfind('.') {
+        Some(dot) => {
+            let (name, extension) = entry.split_at(file_name_start + dot);
+            format!("{name}-legacy{extension}")
+        }
+        None => format!("{entry}-legacy"),
+    }
+}
+
+/// A [`Manifest`] that can be swapped by a freshly parsed version of its file
+/// at runtime. Readers take a snapshot of the current manifest, so they never
+/// observe a half-loaded one.
+#[derive(Debug)]
+pub(crate) struct ReloadableManifest {
+    path: Box<str>,
+    current: RwLock<Arc<Manifest>>,
+}
+
+impl ReloadableManifest {
+    pub fn new(path: &str) -> Result<Self, ViteError> {
+        Ok(ReloadableManifest {
+            path: path.into(),
+            current: RwLock::new(Arc::new(Manifest::new(path)?)),
+        })
+    }
+
+    /// Returns a snapshot of the currently loaded manifest.
+    pub fn current(&self) -> Arc<Manifest> {
+        Arc::clone(&self.current.read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Reads the manifest file again and, if its content hash has changed, parses
+    /// and swaps the current manifest. The new version is parsed before the swap,
+    /// and the write lock is only held for replacing the pointer.
+    ///
+    /// Returns whether the manifest has been swapped.
+    pub fn reload(&self) -> Result<bool, ViteError> {
+        let manifest_content = Manifest::read(&self.path)?;
+        let hash = Manifest::hash(&manifest_content)?;
+
+        if hash == self.current().get_hash() {
+            return Ok(false);
+        }
+
+        let manifest = Manifest::parse_with_hash(&self.path, &manifest_content, hash)?;
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(manifest);
+        Ok(true)
+    }
+}
+
+/// Handle to a background thread that keeps polling the `manifest.json` file
+/// and reloads it whenever its content changes.
+///
+/// The thread stops as soon as this handle is dropped.
+#[derive(Debug)]
+pub struct ManifestWatcher {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ManifestWatcher {
+    pub(crate) fn spawn(manifest: Arc<ReloadableManifest>, poll_interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let should_stop = Arc::clone(&stop);
+
+        let thread = thread::spawn(move || loop {
+            thread::park_timeout(poll_interval);
+
+            if should_stop.load(Ordering::Acquire) {
+                break;
+            }
+
+            match manifest.reload() {
+                Ok(true) => log::info!("Reloaded manifest from {}.", manifest.path),
+                Ok(false) => {}
+                // The file might be in the middle of a write, so it is retried
+                // on the next tick.
+                Err(err) => log::warn!("Failed to reload manifest: {err}"),
+            }
+        });
+
+        ManifestWatcher {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for ManifestWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::Manifest;
+    use super::{Manifest, TagsOptions};
     use crate::{
-        test_utils::NormalizeHtmlStrings,
+        tag::tags_to_html,
+        test_utils::{NormalizeHtmlStrings, TempFile},
         vite::{resolve_app_url, resolve_prefix},
     };
 
@@ -190,7 +650,10 @@ mod test {
             <link rel="modulepreload" href="/assets/shared-B7PI925R.js" />"#
             .__normalize_html_strings();
 
-        let generated = manifest.generate_html_tags(&vec!["views/foo.js".into()], None, "");
+        let tags = manifest
+            .generate_tags(&["views/foo.js"], None, "", &TagsOptions::default(), None)
+            .unwrap();
+        let generated = tags_to_html(&tags);
 
         assert_eq!(expected, generated);
     }
@@ -203,7 +666,10 @@ mod test {
             <link rel="modulepreload" href="/assets/shared-B7PI925R.js" />"#
             .__normalize_html_strings();
 
-        let generated = manifest.generate_html_tags(&vec!["views/bar.js".into()], None, "");
+        let tags = manifest
+            .generate_tags(&["views/bar.js"], None, "", &TagsOptions::default(), None)
+            .unwrap();
+        let generated = tags_to_html(&tags);
 
         assert_eq!(expected, generated);
     }
@@ -211,11 +677,16 @@ mod test {
     #[test]
     fn test_generate_html_tags_with_prefix() {
         let manifest = Manifest::new("tests/test-manifest.json").unwrap();
-        let generated = manifest.generate_html_tags(
-            &vec!["views/bar.js".into()],
-            resolve_prefix(Some("bundle/")),
-            "",
-        );
+        let tags = manifest
+            .generate_tags(
+                &["views/bar.js"],
+                resolve_prefix(Some("bundle/")).as_deref(),
+                "",
+                &TagsOptions::default(),
+                None,
+            )
+            .unwrap();
+        let generated = tags_to_html(&tags);
 
         let expected = r#"<link rel="stylesheet" href="/bundle/assets/shared-ChJ_j-JJ.css" />
             <script type="module" src="/bundle/assets/bar-gkvgaI9m.js"></script>
@@ -228,11 +699,16 @@ mod test {
     #[test]
     fn test_generate_html_tags_with_app_url() {
         let manifest = Manifest::new("tests/test-manifest.json").unwrap();
-        let generated = manifest.generate_html_tags(
-            &vec!["views/bar.js".into()],
-            None,
-            resolve_app_url(Some("http://foo.baz")),
-        );
+        let tags = manifest
+            .generate_tags(
+                &["views/bar.js"],
+                None,
+                &resolve_app_url(Some("http://foo.baz")),
+                &TagsOptions::default(),
+                None,
+            )
+            .unwrap();
+        let generated = tags_to_html(&tags);
 
         let expected =
             r#"<link rel="stylesheet" href="http://foo.baz/assets/shared-ChJ_j-JJ.css" />
@@ -246,11 +722,16 @@ mod test {
     #[test]
     fn test_generate_html_tags_with_prefix_and_app_url() {
         let manifest = Manifest::new("tests/test-manifest.json").unwrap();
-        let generated = manifest.generate_html_tags(
-            &vec!["views/bar.js".into()],
-            resolve_prefix(Some("bundle/")),
-            resolve_app_url(Some("http://foo.baz")),
-        );
+        let tags = manifest
+            .generate_tags(
+                &["views/bar.js"],
+                resolve_prefix(Some("bundle/")).as_deref(),
+                &resolve_app_url(Some("http://foo.baz")),
+                &TagsOptions::default(),
+                None,
+            )
+            .unwrap();
+        let generated = tags_to_html(&tags);
 
         let expected =
             r#"<link rel="stylesheet" href="http://foo.baz/bundle/assets/shared-ChJ_j-JJ.css" />
@@ -261,6 +742,230 @@ mod test {
         assert_eq!(expected, generated);
     }
 
+    #[test]
+    fn test_generate_html_tags_with_integrity() {
+        let manifest = Manifest::new("tests/sri/.vite/manifest.json").unwrap();
+        let tags = manifest
+            .generate_tags(
+                &["src/main.js"],
+                None,
+                "",
+                &TagsOptions {
+                    subresource_integrity: true,
+                    ..Default::default()
+                },
+                None,
+            )
+            .unwrap();
+        let generated = tags_to_html(&tags);
+
+        let expected = r#"<link rel="stylesheet" href="/assets/main-C4nQ1zWe.css" integrity="sha384-OOAocoe9URdSEbKSuC4UfBMdbWnyUwZrR5hgjomiMM3B/YKmdhn8l9D4QtETSrtx" crossorigin="anonymous" />
+            <script type="module" src="/assets/main-Bx2kR9aQ.js" integrity="sha384-YLZ7hFcT7PbOCzXieymmlorgaVLtggYiChPljI46oXxrgkZqICvgIxbogRLS5t6p" crossorigin="anonymous"></script>
+            <link rel="modulepreload" href="/assets/shared-Dk3oP1xL.js" integrity="sha384-from-manifest" crossorigin="anonymous" />"#
+            .__normalize_html_strings();
+
+        assert_eq!(expected, generated);
+    }
+
+    #[test]
+    fn test_integrity_of_missing_file_is_not_cached() {
+        let manifest_file = TempFile::new("integrity-manifest.json");
+        std::fs::write(&manifest_file, "{}").unwrap();
+        let manifest = Manifest::new(manifest_file.to_str()).unwrap();
+
+        let asset = TempFile::new("integrity-asset.js");
+        let file_name = asset.path().file_name().unwrap().to_str().unwrap();
+        assert_eq!(manifest.get_integrity(file_name), None);
+
+        std::fs::write(&asset, "console.log('hello');").unwrap();
+        assert!(manifest
+            .get_integrity(file_name)
+            .is_some_and(|integrity| integrity.starts_with("sha384-")));
+    }
+
+    #[test]
+    fn test_generate_html_tags_with_prefetch() {
+        let manifest = Manifest::new("tests/prefetch-manifest.json").unwrap();
+        let options = TagsOptions {
+            prefetch_depth: Some(1),
+            ..Default::default()
+        };
+
+        let expected = r#"<link rel="stylesheet" href="/assets/shared-Bn8wQe2r.css" />
+            <script type="module" src="/assets/main-B9xRt2Lc.js"></script>
+            <link rel="modulepreload" href="/assets/shared-Cq3vT1mA.js" />
+            <link rel="prefetch" href="/assets/a-C5mNq0Wz.css" />
+            <link rel="prefetch" href="/assets/a-Dk2pLs8v.js" />
+            <link rel="prefetch" href="/assets/b-BfT7yH3e.js" />
+            <link rel="prefetch" href="/assets/lazy-D0sPzK4f.js" />"#
+            .__normalize_html_strings();
+
+        let tags = manifest
+            .generate_tags(&["src/main.js"], None, "", &options, None)
+            .unwrap();
+        let generated = tags_to_html(&tags);
+        assert_eq!(expected, generated);
+
+        // `c` dynamically imports `a` and `b` back, which must not be walked again
+        let tags = manifest
+            .generate_tags(
+                &["src/main.js"],
+                None,
+                "",
+                &TagsOptions {
+                    prefetch_depth: Some(u8::MAX),
+                    ..Default::default()
+                },
+                None,
+            )
+            .unwrap();
+        let generated = tags_to_html(&tags);
+        assert_eq!(1, generated.matches("/assets/c-Ax4Gv9Jd.js").count());
+        assert_eq!(1, generated.matches("/assets/a-Dk2pLs8v.js").count());
+
+        let tags = manifest
+            .generate_tags(
+                &["src/main.js"],
+                None,
+                "",
+                &TagsOptions {
+                    prefetch_depth: Some(0),
+                    ..Default::default()
+                },
+                None,
+            )
+            .unwrap();
+        let without_prefetch = tags_to_html(&tags);
+        assert!(!without_prefetch.contains("prefetch"));
+    }
+
+    #[test]
+    fn test_generate_html_tags_with_legacy_chunks() {
+        let manifest = Manifest::new("tests/legacy-manifest.json").unwrap();
+        let tags = manifest
+            .generate_tags(&["src/main.js"], None, "", &TagsOptions::default(), None)
+            .unwrap();
+        let generated = tags_to_html(&tags);
+
+        let mut lines = generated.lines();
+        assert_eq!(
+            Some(r#"<script type="module" src="/assets/main-Cq1Rz0sd.js"></script>"#),
+            lines.next()
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("<script nomodule>!function(){"));
+        assert_eq!(
+            Some(
+                r#"<script nomodule crossorigin id="vite-legacy-polyfill" src="/assets/polyfills-legacy-BfN2l8pW.js"></script>"#
+            ),
+            lines.next()
+        );
+        assert_eq!(
+            Some(
+                r#"<script nomodule crossorigin id="vite-legacy-entry" data-src="/assets/main-legacy-D4tVx7yA.js">System.import(document.getElementById('vite-legacy-entry').getAttribute('data-src'))</script>"#
+            ),
+            lines.next()
+        );
+        assert_eq!(None, lines.next());
+
+        assert_eq!(vec!["src/main.js"], manifest.get_manifest_entries());
+    }
+
+    #[test]
+    fn test_generate_html_tags_with_nested_imports() {
+        let manifest = Manifest::new("tests/nested-manifest.json").unwrap();
+        let tags = manifest
+            .generate_tags(&["src/main.js"], None, "", &TagsOptions::default(), None)
+            .unwrap();
+        let generated = tags_to_html(&tags);
+
+        let expected = r#"<link rel="stylesheet" href="/assets/vendor-D9sKq1Lw.css" />
+            <link rel="stylesheet" href="/assets/vendor-ui-Bm4nR8cX.css" />
+            <script type="module" src="/assets/main-DkP3w9Ya.js"></script>
+            <link rel="modulepreload" href="/assets/vendor-Cx8aLm2q.js" />
+            <link rel="modulepreload" href="/assets/vendor-ui-B1qT0pZe.js" />"#
+            .__normalize_html_strings();
+
+        assert_eq!(expected, generated);
+    }
+
+    #[test]
+    fn test_generate_html_tags_with_missing_import() {
+        let manifest = Manifest::new("tests/nested-manifest.json").unwrap();
+        let error = manifest
+            .generate_tags(&["src/broken.js"], None, "", &TagsOptions::default(), None)
+            .unwrap_err();
+
+        assert!(error.to_string().contains("_missing-A1b2C3d4.js"));
+    }
+
+    #[test]
+    fn test_generate_html_tags_with_style_sheet_entries() {
+        let manifest = Manifest::new("tests/styles-manifest.json").unwrap();
+        let options = TagsOptions {
+            style_sheet_extensions: [".scss".to_string()].into(),
+            ..Default::default()
+        };
+
+        // `.styl` isn't listed, but the entry is bundled into a `.css` file anyway
+        let tags = manifest
+            .generate_tags(
+                &["src/app.scss", "src/theme.styl", "src/main.ts"],
+                None,
+                "",
+                &options,
+                None,
+            )
+            .unwrap();
+        let generated = tags_to_html(&tags);
+
+        let expected = r#"<link rel="stylesheet" href="/assets/app-Dq3mX9aK.css" />
+            <link rel="stylesheet" href="/assets/theme-P7vLs2Qe.css" />
+            <script type="module" src="/assets/main-Hc8wN4rT.js"></script>"#
+            .__normalize_html_strings();
+
+        assert_eq!(expected, generated);
+    }
+
+    #[test]
+    fn test_generate_link_headers() {
+        let manifest = Manifest::new("tests/test-manifest.json").unwrap();
+        let generated = manifest
+            .generate_link_headers(
+                &["views/foo.js"],
+                resolve_prefix(Some("bundle/")).as_deref(),
+                &resolve_app_url(Some("http://foo.baz")),
+                &TagsOptions::default(),
+            )
+            .unwrap();
+
+        let expected = vec![
+            "<http://foo.baz/bundle/assets/foo-5UjPuW-k.css>; rel=preload; as=style",
+            "<http://foo.baz/bundle/assets/shared-ChJ_j-JJ.css>; rel=preload; as=style",
+            "<http://foo.baz/bundle/assets/foo-BRBmoGS9.js>; rel=modulepreload",
+            "<http://foo.baz/bundle/assets/shared-B7PI925R.js>; rel=modulepreload",
+        ];
+
+        assert_eq!(expected, generated);
+
+        let generated = Manifest::new("tests/prefetch-manifest.json")
+            .unwrap()
+            .generate_link_headers(
+                &["src/main.js"],
+                None,
+                "",
+                &TagsOptions {
+                    prefetch_depth: Some(2),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert!(generated.iter().all(|header| !header.contains("prefetch")));
+    }
+
     #[test]
     fn test_get_asset_url() {
         let manifest = Manifest::new("tests/test-manifest.json").unwrap();
@@ -274,7 +979,8 @@ mod test {
     #[test]
     fn test_get_asset_url_with_prefix() {
         let manifest = Manifest::new("tests/test-manifest.json").unwrap();
-        let generated = manifest.get_asset_url("baz.js", resolve_prefix(Some("bundle/")), "");
+        let generated =
+            manifest.get_asset_url("baz.js", resolve_prefix(Some("bundle/")).as_deref(), "");
 
         let expected = "/bundle/assets/baz-B2H3sXNv.js";
 
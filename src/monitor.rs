@@ -0,0 +1,195 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::utils::{is_dev_server_up, read_hot_file};
+use crate::{Vite, ViteMode};
+
+/// Handle to a background thread that keeps checking whether the Vite dev-server
+/// is running, and switches the monitored [`Vite`] instance between `Development`
+/// and `Manifest` modes accordingly.
+///
+/// The thread stops as soon as this handle is dropped.
+#[derive(Debug)]
+pub struct DevServerMonitor {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DevServerMonitor {
+    pub(crate) fn spawn(vite: Vite, poll_interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let should_stop = Arc::clone(&stop);
+
+        let thread = thread::spawn(move || loop {
+            // `None` if the hot file can't be read, e.g. while it's being written
+            let is_up = match &vite.hot_file_path {
+                Some(hot_file_path) => match read_hot_file(hot_file_path) {
+                    Ok(Some(dev_server_host)) => {
+                        vite.set_dev_server_host(&dev_server_host);
+                        Some(true)
+                    }
+                    Ok(None) => Some(false),
+                    Err(err) => {
+                        log::warn!("Failed to read the hot file while monitoring: {err}");
+                        None
+                    }
+                },
+                None => Some(is_dev_server_up(
+                    &vite.dev_server_host(),
+                    vite.heart_beat_timeout,
+                    &vite.dev_server_tls,
+                )),
+            };
+
+            if should_stop.load(Ordering::Acquire) {
+                break;
+            }
+
+            if let Some(is_up) = is_up {
+                vite.switch_mode(match is_up {
+                    true => ViteMode::Development,
+                    false => ViteMode::Manifest,
+                });
+            }
+
+            thread::park_timeout(poll_interval);
+
+            if should_stop.load(Ordering::Acquire) {
+                break;
+            }
+        });
+
+        DevServerMonitor {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for DevServerMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::time::Duration;
+    use std::{fs, thread};
+
+    use crate::test_utils::TempFile;
+    use crate::{Vite, ViteConfig, ViteMode};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_monitor_hot_file() {
+        let hot_file = TempFile::new("monitor-hot-file");
+
+        let vite = Vite::new_blocking(
+            ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+                .set_hot_file_path(hot_file.to_str()),
+        )
+        .unwrap();
+        assert_eq!(ViteMode::Manifest, vite.mode());
+
+        let changes = vite.subscribe_mode();
+        let _monitor = vite.monitor_dev_server(Duration::from_millis(10));
+
+        fs::write(&hot_file, "http://localhost:5173").unwrap();
+        assert_eq!(Ok(ViteMode::Development), changes.recv_timeout(TIMEOUT));
+        assert_eq!(ViteMode::Development, vite.clone().mode());
+
+        fs::remove_file(&hot_file).unwrap();
+        assert_eq!(Ok(ViteMode::Manifest), changes.recv_timeout(TIMEOUT));
+        assert_eq!(
+            "/assets/foo-BRBmoGS9.js",
+            vite.get_asset_url("views/foo.js").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_monitor_hot_file_dev_server_url() {
+        let hot_file = TempFile::new("monitor-hot-file-url");
+
+        let vite = Vite::new_blocking(
+            ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+                .set_hot_file_path(hot_file.to_str()),
+        )
+        .unwrap();
+        assert_eq!("http://localhost:5173", vite.get_dev_server_url());
+
+        let changes = vite.subscribe_mode();
+        let _monitor = vite.monitor_dev_server(Duration::from_millis(10));
+
+        fs::write(&hot_file, "http://localhost:5174").unwrap();
+        assert_eq!(Ok(ViteMode::Development), changes.recv_timeout(TIMEOUT));
+        assert_eq!("http://localhost:5174", vite.get_dev_server_url());
+        assert_eq!(
+            "http://localhost:5174/views/foo.js",
+            vite.get_asset_url("views/foo.js").unwrap()
+        );
+
+        // the dev-server restarts on another port without the mode changing
+        fs::write(&hot_file, "http://localhost:5175/").unwrap();
+        let mut dev_server_url = String::new();
+        for _ in 0..500 {
+            dev_server_url = vite.get_dev_server_url();
+            if dev_server_url != "http://localhost:5174" {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!("http://localhost:5175", dev_server_url);
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_monitor_dev_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_host = format!("http://{}", listener.local_addr().unwrap());
+
+        // starts in Development mode, without any manifest loaded
+        let vite = Vite::new_blocking(
+            ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+                .set_server_host(&server_host)
+                .set_heart_beat_timeout(Duration::from_millis(100))
+                .set_force_mode(ViteMode::Development),
+        )
+        .unwrap();
+
+        let changes = vite.subscribe_mode();
+        let monitor = vite.monitor_dev_server(Duration::from_millis(10));
+
+        // nobody answers yet
+        assert_eq!(Ok(ViteMode::Manifest), changes.recv_timeout(TIMEOUT));
+        assert_eq!(
+            "/assets/foo-BRBmoGS9.js",
+            vite.get_asset_url("views/foo.js").unwrap()
+        );
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).unwrap();
+                let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+            }
+        });
+
+        assert_eq!(Ok(ViteMode::Development), changes.recv_timeout(TIMEOUT));
+        assert_eq!(ViteMode::Development, vite.mode());
+
+        drop(monitor);
+        assert!(changes.recv_timeout(Duration::from_millis(50)).is_err());
+    }
+}
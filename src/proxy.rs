@@ -0,0 +1,608 @@
+use std::fmt::{Debug, Display};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use http::header::{HeaderMap, CONTENT_LENGTH, HOST, SEC_WEBSOCKET_PROTOCOL, UPGRADE};
+use http::{Request, Response, StatusCode};
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::error::{ViteError, ViteErrorKind};
+use crate::utils::{host_name, split_url};
+use crate::{Vite, ViteMode};
+
+/// The paths served by the Vite dev-server regardless of the project's sources.
+pub const DEFAULT_PROXIED_PATHS: [&str; 5] = [
+    "/@vite/",
+    "/@react-refresh",
+    "/@id/",
+    "/@fs/",
+    "/node_modules/",
+];
+
+/// The WebSocket sub-protocols used by the Vite client to reach the dev-server.
+const HMR_PROTOCOLS: [&str; 2] = ["vite-hmr", "vite-ping"];
+
+/// Headers that only make sense for a single connection, and so are never forwarded.
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// The largest response head accepted from the dev-server when upgrading the HMR
+/// connection.
+const MAX_HEAD_LENGTH: usize = 16 * 1024;
+
+/// A reverse proxy to the Vite dev-server, so that the application serves the
+/// dev-server assets and HMR WebSocket from its own origin. That spares setting
+/// CORS up, and works when the dev-server is not reachable from the browser, e.g.
+/// inside a container.
+///
+/// It is framework-agnostic: requests are [`http::Request`]s and responses are
+/// [`http::Response`]s, which most Rust web frameworks convert from and to.
+///
+/// Requests are only proxied while in `Development` mode.
+///
+/// # Example
+/// ```rust
+/// use vite_rust::{Vite, ViteConfig, ViteMode};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let vite = Vite::new(
+///         ViteConfig::default()
+///             .set_entrypoints(["src/main.ts"])
+///             .set_force_mode(ViteMode::Development)
+///             .set_proxy_dev_server(true),
+///     )
+///     .await
+///     .unwrap();
+///
+///     assert_eq!("/src/main.ts", vite.get_asset_url("src/main.ts").unwrap());
+///
+///     let proxy = vite.dev_server_proxy().unwrap();
+///     assert!(proxy.should_proxy("/@vite/client"));
+///     assert!(proxy.should_proxy("/src/App.tsx"));
+///     assert!(!proxy.should_proxy("/users/1"));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DevServerProxy {
+    vite: Vite,
+    /// The HTTP client, along with the dev-server host its TLS policy was set up for.
+    client: Arc<Mutex<(Arc<str>, reqwest::Client)>>,
+    paths: Vec<String>,
+}
+
+impl DevServerProxy {
+    pub(crate) fn new(vite: Vite) -> Result<Self, ViteError> {
+        let dev_host = vite.dev_server_host();
+        let client = DevServerProxy::build_client(&vite, &dev_host)?;
+
+        let mut paths: Vec<String> = DEFAULT_PROXIED_PATHS.map(String::from).to_vec();
+        for entrypoint in vite.entrypoints() {
+            let entrypoint = entrypoint.strip_prefix('/').unwrap_or(entrypoint);
+            let path = match entrypoint.split_once('/') {
+                Some((directory, _)) => format!("/{directory}/"),
+                None => format!("/{entrypoint}"),
+            };
+
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+
+        Ok(DevServerProxy {
+            vite,
+            client: Arc::new(Mutex::new((dev_host, client))),
+            paths,
+        })
+    }
+
+    fn build_client(vite: &Vite, dev_host: &str) -> Result<reqwest::Client, ViteError> {
+        vite.dev_server_tls
+            .reqwest_client(dev_host, reqwest::redirect::Policy::none())
+    }
+
+    /// Returns the HTTP client for `dev_host`, setting up a new one if the dev-server
+    /// has moved since, as invalid certificates are only accepted from local hosts.
+    fn client(&self, dev_host: &Arc<str>) -> Result<reqwest::Client, ViteError> {
+        let mut client = self.client.lock().unwrap_or_else(PoisonError::into_inner);
+        if client.0 != *dev_host {
+            *client = (
+                Arc::clone(dev_host),
+                DevServerProxy::build_client(&self.vite, dev_host)?,
+            );
+        }
+
+        Ok(client.1.clone())
+    }
+
+    /// Proxies the paths starting with `path` as well, e.g. `/assets/` for files
+    /// imported from outside the entrypoints' directories.
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    /// Returns the path prefixes forwarded to the dev-server: the
+    /// [`DEFAULT_PROXIED_PATHS`], the entrypoints' root directories and the ones
+    /// added by [`DevServerProxy::with_path`].
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    /// Checks whether a request to `path` must be forwarded to the dev-server.
+    pub fn should_proxy(&self, path: &str) -> bool {
+        self.vite.mode() == ViteMode::Development
+            && self.paths.iter().any(|prefix| path.starts_with(prefix))
+    }
+
+    /// Checks whether the request is the Vite client opening its HMR WebSocket,
+    /// which must be tunnelled through [`DevServerProxy::connect_hmr`].
+    pub fn is_hmr_upgrade<B>(&self, request: &Request<B>) -> bool {
+        let is_websocket = request
+            .headers()
+            .get(UPGRADE)
+            .and_then(|upgrade| upgrade.to_str().ok())
+            .is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+
+        let is_vite_client = request
+            .headers()
+            .get_all(SEC_WEBSOCKET_PROTOCOL)
+            .iter()
+            .filter_map(|protocols| protocols.to_str().ok())
+            .flat_map(|protocols| protocols.split(','))
+            .any(|protocol| HMR_PROTOCOLS.contains(&protocol.trim()));
+
+        self.vite.mode() == ViteMode::Development && is_websocket && is_vite_client
+    }
+
+    /// Forwards the request to the dev-server, returning its response.
+    ///
+    /// # Errors
+    /// Returns a `DevServerUnreachable` error if the dev-server can't be reached, or
+    /// an `InvalidConfig` error if no HTTP client can be set up for its new URL.
+    pub async fn forward<B>(&self, request: Request<B>) -> Result<Response<Vec<u8>>, ViteError>
+    where
+        B: Into<reqwest::Body>,
+    {
+        let dev_host = self.vite.dev_server_host();
+        let client = self.client(&dev_host)?;
+
+        let (parts, body) = request.into_parts();
+        let path = parts.uri.path_and_query().map_or("/", |path| path.as_str());
+        let url = format!("{dev_host}{path}");
+
+        let mut headers = parts.headers;
+        remove_hop_by_hop_headers(&mut headers);
+        headers.remove(HOST);
+
+        let upstream = client
+            .request(parts.method, &url)
+            .headers(headers)
+            .body(body)
+            .send()
+            .await
+            .map_err(|err| self.unreachable(&url, err))?;
+
+        let status = upstream.status();
+        let mut headers = upstream.headers().clone();
+        remove_hop_by_hop_headers(&mut headers);
+
+        let body = upstream
+            .bytes()
+            .await
+            .map_err(|err| self.unreachable(&url, err))?;
+
+        let mut response = Response::new(body.to_vec());
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+
+        Ok(response)
+    }
+
+    /// Forwards the HMR WebSocket upgrade request to the dev-server. The returned
+    /// [`HmrTunnel`] holds the dev-server's `101 Switching Protocols` response,
+    /// which must be sent back to the browser before running the tunnel over the
+    /// upgraded connection.
+    ///
+    /// # Errors
+    /// Returns a `DevServerUnreachable` error if Vite isn't in `Development` mode, or
+    /// if the dev-server can't be reached, its certificate isn't trusted or it refuses
+    /// the upgrade.
+    pub async fn connect_hmr<B>(&self, request: &Request<B>) -> Result<HmrTunnel, ViteError> {
+        let dev_host = &*self.vite.dev_server_host();
+        if self.vite.mode() != ViteMode::Development {
+            return Err(self.unreachable(dev_host, "Vite isn't in Development mode"));
+        }
+
+        let (scheme, authority, address, _) =
+            split_url(dev_host).map_err(|err| self.unreachable(dev_host, err))?;
+
+        let stream = TcpStream::connect(&address)
+            .await
+            .map_err(|err| self.unreachable(dev_host, err))?;
+
+        let mut upstream: Box<dyn Connection> = match scheme {
+            "https" => {
+                let host = host_name(authority);
+                let connector = self
+                    .vite
+                    .dev_server_tls
+                    .connector(host)
+                    .map_err(|err| self.unreachable(dev_host, err))?;
+
+                Box::new(
+                    tokio_native_tls::TlsConnector::from(connector)
+                        .connect(host, stream)
+                        .await
+                        .map_err(|err| self.unreachable(dev_host, err))?,
+                )
+            }
+            _ => Box::new(stream),
+        };
+
+        let path = request
+            .uri()
+            .path_and_query()
+            .map_or("/", |path| path.as_str());
+
+        let mut head = format!(
+            "{} {path} HTTP/1.1\r\nHost: {authority}\r\n",
+            request.method()
+        )
+        .into_bytes();
+        for (name, value) in request.headers().iter().filter(|(name, _)| **name != HOST) {
+            head.extend_from_slice(name.as_str().as_bytes());
+            head.extend_from_slice(b": ");
+            head.extend_from_slice(value.as_bytes());
+            head.extend_from_slice(b"\r\n");
+        }
+        head.extend_from_slice(b"\r\n");
+
+        upstream
+            .write_all(&head)
+            .await
+            .map_err(|err| self.unreachable(dev_host, err))?;
+
+        let mut buffer = Vec::new();
+        loop {
+            let mut chunk = [0; 1024];
+            let read = upstream
+                .read(&mut chunk)
+                .await
+                .map_err(|err| self.unreachable(dev_host, err))?;
+
+            if read == 0 {
+                return Err(self.unreachable(
+                    dev_host,
+                    "the connection was closed before the response head was received",
+                ));
+            }
+            if buffer.len() + read > MAX_HEAD_LENGTH {
+                return Err(self.unreachable(
+                    dev_host,
+                    format!("the response head is longer than {MAX_HEAD_LENGTH} bytes"),
+                ));
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+
+            let mut headers = [httparse::EMPTY_HEADER; 64];
+            let mut parsed = httparse::Response::new(&mut headers);
+            let head_length = match parsed.parse(&buffer) {
+                Ok(httparse::Status::Complete(head_length)) => head_length,
+                Ok(httparse::Status::Partial) => continue,
+                Err(err) => return Err(self.unreachable(dev_host, err)),
+            };
+
+            let status = parsed
+                .code
+                .and_then(|code| StatusCode::from_u16(code).ok())
+                .unwrap_or(StatusCode::BAD_GATEWAY);
+
+            if status != StatusCode::SWITCHING_PROTOCOLS {
+                return Err(self.unreachable(
+                    dev_host,
+                    format!("the HMR WebSocket upgrade was refused with {status}"),
+                ));
+            }
+
+            let mut response_headers = HeaderMap::new();
+            for header in parsed.headers.iter() {
+                if let (Ok(name), Ok(value)) = (
+                    http::HeaderName::from_bytes(header.name.as_bytes()),
+                    http::HeaderValue::from_bytes(header.value),
+                ) {
+                    response_headers.append(name, value);
+                }
+            }
+
+            return Ok(HmrTunnel {
+                status,
+                headers: response_headers,
+                leftover: buffer[head_length..].to_vec(),
+                upstream,
+            });
+        }
+    }
+
+    fn unreachable(&self, url: &str, err: impl Display) -> ViteError {
+        ViteError::new(
+            format!("Failed to proxy the request to the Vite dev-server at {url}: {err}"),
+            ViteErrorKind::DevServerUnreachable {
+                url: self.vite.dev_server_host().to_string(),
+            },
+        )
+    }
+}
+
+/// An HMR WebSocket connection to the dev-server, created by
+/// [`DevServerProxy::connect_hmr`].
+#[derive(Debug)]
+pub struct HmrTunnel {
+    status: StatusCode,
+    headers: HeaderMap,
+    leftover: Vec<u8>,
+    upstream: Box<dyn Connection>,
+}
+
+/// The connection to the dev-server, over TLS if it is served over `https`.
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send + Sync + Debug {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Sync + Debug> Connection for T {}
+
+impl HmrTunnel {
+    /// Returns the dev-server's response to the upgrade request, to send back to
+    /// the browser.
+    pub fn response(&self) -> Response<()> {
+        let mut response = Response::new(());
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        response
+    }
+
+    /// Copies the WebSocket frames between the upgraded browser connection and the
+    /// dev-server until either side closes.
+    ///
+    /// # Errors
+    /// Returns an `Io` error if either connection fails.
+    pub async fn run<IO>(mut self, mut client: IO) -> Result<(), ViteError>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        let to_io_error = |err: io::Error| {
+            ViteError::with_source("The HMR WebSocket tunnel failed.", ViteErrorKind::Io, err)
+        };
+
+        client
+            .write_all(&self.leftover)
+            .await
+            .map_err(to_io_error)?;
+        io::copy_bidirectional(&mut client, &mut self.upstream)
+            .await
+            .map_err(to_io_error)?;
+
+        Ok(())
+    }
+}
+
+fn remove_hop_by_hop_headers(headers: &mut HeaderMap) {
+    for name in HOP_BY_HOP_HEADERS {
+        headers.remove(name);
+    }
+    headers.remove(CONTENT_LENGTH);
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use http::Request;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::MAX_HEAD_LENGTH;
+    use crate::{Vite, ViteConfig, ViteMode};
+
+    /// Accepts a single connection, returning what was received along with the
+    /// server's address.
+    fn serve_once(
+        response: impl AsRef<[u8]> + Send + 'static,
+    ) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let read = stream.read(&mut request).unwrap();
+            stream.write_all(response.as_ref()).unwrap();
+            String::from_utf8_lossy(&request[..read]).into_owned()
+        });
+
+        (host, server)
+    }
+
+    fn get_vite(host: &str, mode: ViteMode) -> Vite {
+        Vite::new_blocking(
+            ViteConfig::default()
+                .set_entrypoints(["src/main.ts", "/resources/app.css"])
+                .set_server_host(host)
+                .set_force_mode(mode)
+                .set_proxy_dev_server(true),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_should_proxy() {
+        let vite = get_vite("http://localhost:5173", ViteMode::Development);
+
+        assert_eq!("http://localhost:5173", vite.get_dev_server_url());
+        assert_eq!("/src/main.ts", vite.get_asset_url("src/main.ts").unwrap());
+        assert!(vite.get_hmr_script().contains(r#"src="/@vite/client""#));
+
+        let proxy = vite.dev_server_proxy().unwrap().with_path("/assets/");
+        assert!(proxy.paths().contains(&"/resources/".to_string()));
+        assert!(proxy.should_proxy("/@vite/client"));
+        assert!(proxy.should_proxy("/node_modules/.vite/deps/react.js?v=f3a1"));
+        assert!(proxy.should_proxy("/resources/app.css"));
+        assert!(proxy.should_proxy("/assets/logo.svg"));
+        assert!(!proxy.should_proxy("/"));
+        assert!(!proxy.should_proxy("/api/users"));
+
+        let upgrade = Request::get("/?token=x")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Protocol", "vite-hmr")
+            .body(())
+            .unwrap();
+        assert!(proxy.is_hmr_upgrade(&upgrade));
+        assert!(!proxy.is_hmr_upgrade(&Request::get("/").body(()).unwrap()));
+
+        let vite = Vite::new_blocking(
+            ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+                .set_force_mode(ViteMode::Manifest)
+                .set_proxy_dev_server(true),
+        )
+        .unwrap();
+        let proxy = vite.dev_server_proxy().unwrap();
+        assert!(!proxy.should_proxy("/@vite/client"));
+        assert!(!proxy.is_hmr_upgrade(&upgrade));
+    }
+
+    #[tokio::test]
+    async fn test_forward() {
+        let (host, server) = serve_once(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/javascript\r\nContent-Length: 15\r\nConnection: close\r\n\r\nexport default;",
+        );
+        let proxy = get_vite(&host, ViteMode::Development)
+            .dev_server_proxy()
+            .unwrap();
+
+        let request = Request::get("/src/main.ts?t=1")
+            .header("Host", "localhost:8000")
+            .header("Accept", "*/*")
+            .body(Vec::new())
+            .unwrap();
+        let response = proxy.forward(request).await.unwrap();
+
+        assert_eq!(200, response.status());
+        assert_eq!("text/javascript", response.headers()["content-type"]);
+        assert!(response.headers().get("connection").is_none());
+        assert_eq!(b"export default;", response.body().as_slice());
+
+        let received = server.join().unwrap().to_lowercase();
+        assert!(received.starts_with("get /src/main.ts?t=1 http/1.1\r\n"));
+        assert!(received.contains(&format!("host: {}\r\n", &host[7..])));
+        assert!(!received.contains("localhost:8000"));
+    }
+
+    #[tokio::test]
+    async fn test_forward_after_dev_server_moved() {
+        let vite = get_vite("http://127.0.0.1:1", ViteMode::Development);
+        let proxy = vite.dev_server_proxy().unwrap();
+
+        let (host, server) = serve_once(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n");
+        vite.set_dev_server_host(&host);
+
+        let request = Request::get("/@vite/client").body(Vec::new()).unwrap();
+        let response = proxy.forward(request).await.unwrap();
+        assert_eq!(204, response.status());
+
+        let received = server.join().unwrap().to_lowercase();
+        assert!(received.starts_with("get /@vite/client http/1.1\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_forward_errors() {
+        let proxy = get_vite("http://127.0.0.1:1", ViteMode::Development)
+            .dev_server_proxy()
+            .unwrap();
+        let request = Request::get("/@vite/client").body(Vec::new()).unwrap();
+
+        let error = proxy.forward(request).await.unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            crate::error::ViteErrorKind::DevServerUnreachable { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_hmr_tunnel() {
+        let (host, server) = serve_once(
+            b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\nSec-WebSocket-Protocol: vite-hmr\r\n\r\n\x81\x0f{\"type\":\"ping\"}",
+        );
+        let proxy = get_vite(&host, ViteMode::Development)
+            .dev_server_proxy()
+            .unwrap();
+
+        let request = Request::get("/?token=x")
+            .header("Host", "localhost:8000")
+            .header("Upgrade", "websocket")
+            .header("Connection", "Upgrade")
+            .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+            .header("Sec-WebSocket-Protocol", "vite-hmr")
+            .body(())
+            .unwrap();
+
+        let tunnel = proxy.connect_hmr(&request).await.unwrap();
+        let response = tunnel.response();
+        assert_eq!(101, response.status());
+        assert_eq!(
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=",
+            response.headers()["sec-websocket-accept"]
+        );
+
+        let (mut browser, upgraded) = tokio::io::duplex(1024);
+        let running = tokio::spawn(tunnel.run(upgraded));
+
+        let mut frame = [0; 17];
+        browser.read_exact(&mut frame).await.unwrap();
+        assert_eq!(b"\x81\x0f{\"type\":\"ping\"}", &frame);
+
+        browser.shutdown().await.unwrap();
+        drop(browser);
+        running.await.unwrap().unwrap();
+
+        let received = server.join().unwrap().to_lowercase();
+        assert!(received.starts_with("get /?token=x http/1.1\r\n"));
+        assert!(received.contains("sec-websocket-protocol: vite-hmr\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_connect_hmr_errors() {
+        let request = Request::get("/?token=x")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Protocol", "vite-hmr")
+            .body(())
+            .unwrap();
+
+        let vite = Vite::new_blocking(
+            ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+                .set_force_mode(ViteMode::Manifest)
+                .set_proxy_dev_server(true),
+        )
+        .unwrap();
+        let proxy = vite.dev_server_proxy().unwrap();
+        let error = proxy.connect_hmr(&request).await.unwrap_err();
+        assert!(error.to_string().contains("Development mode"));
+
+        let mut response = b"HTTP/1.1 101 Switching Protocols\r\nX-Padding: ".to_vec();
+        response.resize(MAX_HEAD_LENGTH + 1024, b'a');
+        let (host, server) = serve_once(response);
+        let proxy = get_vite(&host, ViteMode::Development)
+            .dev_server_proxy()
+            .unwrap();
+
+        let error = proxy.connect_hmr(&request).await.unwrap_err();
+        assert!(error.to_string().contains("response head is longer than"));
+        server.join().unwrap();
+    }
+}
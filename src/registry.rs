@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use crate::{
+    error::{ViteError, ViteErrorKind},
+    Vite, ViteConfig,
+};
+
+/// A set of named [`Vite`] builds living in the same process, such as an
+/// "admin" and a "storefront" bundle, each with its own manifest, dev-server
+/// and prefix.
+///
+/// Every build is set up by [`Vite::new`], so they resolve their modes
+/// independently: the admin dev-server might be running while the storefront
+/// is served from its manifest.
+///
+/// # Example
+/// ```rust
+/// use vite_rust::{ViteConfig, ViteMode, ViteRegistry};
+///
+/// fn main() {
+///     let mut registry = ViteRegistry::new();
+///
+///     registry
+///         .register_blocking(
+///             "storefront",
+///             ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+///                 .set_force_mode(ViteMode::Manifest),
+///         )
+///         .unwrap();
+///
+///     let storefront = registry.try_get("storefront").unwrap();
+///     assert_eq!(ViteMode::Manifest, storefront.mode());
+///     assert!(registry.get("admin").is_none());
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ViteRegistry {
+    builds: HashMap<String, Vite>,
+}
+
+impl ViteRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets up a [`Vite`] instance from the given config and registers it
+    /// under `name`, replacing any build previously registered with that name.
+    ///
+    /// # Errors
+    /// Returns the [`ViteError`] raised by [`Vite::new`].
+    #[cfg(feature = "async")]
+    pub async fn register(
+        &mut self,
+        name: impl Into<String>,
+        config: ViteConfig,
+    ) -> Result<&Vite, ViteError> {
+        let vite = Vite::new(config).await;
+        self.insert_set_up(name.into(), vite)
+    }
+
+    /// Does the same as [`ViteRegistry::register`], setting the build up with
+    /// [`Vite::new_blocking`].
+    ///
+    /// # Errors
+    /// Returns the [`ViteError`] raised by [`Vite::new_blocking`].
+    pub fn register_blocking(
+        &mut self,
+        name: impl Into<String>,
+        config: ViteConfig,
+    ) -> Result<&Vite, ViteError> {
+        let vite = Vite::new_blocking(config);
+        self.insert_set_up(name.into(), vite)
+    }
+
+    fn insert_set_up(
+        &mut self,
+        name: String,
+        vite: Result<Vite, ViteError>,
+    ) -> Result<&Vite, ViteError> {
+        let vite = vite.map_err(|err| {
+            let cause = format!("Failed to set up the \"{name}\" Vite build: {err}");
+            let kind = err.kind().clone();
+            ViteError::with_source(cause, kind, err)
+        })?;
+
+        self.builds.insert(name.clone(), vite);
+        Ok(&self.builds[&name])
+    }
+
+    /// Registers an already set up [`Vite`] instance under `name`, returning
+    /// the build it replaced, if any.
+    pub fn insert(&mut self, name: impl Into<String>, vite: Vite) -> Option<Vite> {
+        self.builds.insert(name.into(), vite)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Vite> {
+        self.builds.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Vite> {
+        self.builds.get(name)
+    }
+
+    /// Does the same as [`ViteRegistry::get`], but returns an
+    /// [`ViteErrorKind::UnknownBuild`] error if there is no such a build.
+    pub fn try_get(&self, name: &str) -> Result<&Vite, ViteError> {
+        self.builds.get(name).ok_or_else(|| {
+            ViteError::new(
+                format!("There is no Vite build named \"{name}\" in the registry."),
+                ViteErrorKind::UnknownBuild {
+                    name: name.to_string(),
+                },
+            )
+        })
+    }
+
+    /// Returns an iterator over the registered builds' names, in arbitrary order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.builds.keys().map(String::as_str)
+    }
+
+    /// Returns an iterator over the registered builds, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Vite)> {
+        self.builds.iter().map(|(name, vite)| (name.as_str(), vite))
+    }
+
+    pub fn len(&self) -> usize {
+        self.builds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.builds.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ViteRegistry;
+    use crate::{error::ViteErrorKind, ViteConfig, ViteMode};
+
+    fn get_registry() -> ViteRegistry {
+        let mut registry = ViteRegistry::new();
+
+        registry
+            .register_blocking(
+                "admin",
+                ViteConfig::default()
+                    .set_entrypoints(["admin/main.js"])
+                    .set_server_host("http://localhost:5174")
+                    .set_force_mode(ViteMode::Development),
+            )
+            .unwrap();
+
+        registry
+            .register_blocking(
+                "storefront",
+                ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+                    .set_prefix("shop")
+                    .set_force_mode(ViteMode::Manifest),
+            )
+            .unwrap();
+
+        registry
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = get_registry();
+
+        let admin = registry.get("admin").unwrap();
+        assert_eq!(ViteMode::Development, admin.mode());
+        assert_eq!("http://localhost:5174", admin.get_dev_server_url());
+
+        let storefront = registry.try_get("storefront").unwrap();
+        assert_eq!(ViteMode::Manifest, storefront.mode());
+        assert_eq!(
+            "/shop/assets/baz-B2H3sXNv.js",
+            storefront.get_asset_url("baz.js").unwrap()
+        );
+
+        let mut names = registry.names().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(vec!["admin", "storefront"], names);
+
+        let error = registry.try_get("blog").unwrap_err();
+        assert_eq!(
+            &ViteErrorKind::UnknownBuild {
+                name: "blog".into()
+            },
+            error.kind()
+        );
+    }
+
+    #[test]
+    fn test_register_errors() {
+        let mut registry = ViteRegistry::new();
+
+        let error = registry
+            .register_blocking(
+                "admin",
+                ViteConfig::new("tests/missing-manifest.json", ["admin/main.js"])
+                    .set_force_mode(ViteMode::Manifest),
+            )
+            .unwrap_err();
+
+        assert!(matches!(error.kind(), ViteErrorKind::MissingFile { .. }));
+        assert!(error.to_string().contains("\"admin\""));
+        assert!(registry.is_empty());
+    }
+}
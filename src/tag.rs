@@ -0,0 +1,273 @@
+use std::fmt;
+
+/// What a [`Tag`] references, and hence which HTML element it's rendered as.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum TagKind {
+    /// A `<link rel="stylesheet">` tag.
+    StyleSheet,
+    /// A `<script type="module">` tag referencing an entrypoint or client script.
+    ModuleScript,
+    /// A `<link rel="modulepreload">` tag.
+    ModulePreload,
+    /// A `<link rel="preload">` tag for a non-JavaScript asset (image, font...).
+    Preload,
+    /// A `<link rel="prefetch">` tag for a lazily-loaded chunk.
+    Prefetch,
+    /// A `<script nomodule>` tag loading the `@vitejs/plugin-legacy` polyfills.
+    LegacyPolyfills,
+    /// A `<script nomodule>` tag importing a legacy entry from its `data-src`.
+    LegacyEntry,
+    /// A `<script>` tag with inline content and no URL.
+    InlineScript,
+}
+
+impl TagKind {
+    /// The HTML element name the tag is rendered as.
+    pub fn element(&self) -> &'static str {
+        match self {
+            Self::StyleSheet | Self::ModulePreload | Self::Preload | Self::Prefetch => "link",
+            Self::ModuleScript | Self::LegacyPolyfills | Self::LegacyEntry | Self::InlineScript => {
+                "script"
+            }
+        }
+    }
+
+    /// The attribute holding the tag's URL, if any.
+    pub fn url_attribute(&self) -> Option<&'static str> {
+        match self {
+            Self::StyleSheet | Self::ModulePreload | Self::Preload | Self::Prefetch => Some("href"),
+            Self::ModuleScript | Self::LegacyPolyfills => Some("src"),
+            Self::LegacyEntry => Some("data-src"),
+            Self::InlineScript => None,
+        }
+    }
+}
+
+/// A structured HTML tag generated by vite-rust, with an ordered attributes map.
+///
+/// Tags can be changed before being rendered (e.g. adding `defer`, `data-*` or
+/// `fetchpriority` attributes), or be fed into a custom renderer. The default
+/// renderer, [`Tag::to_html`], produces the same HTML as the string methods of
+/// [`Vite`].
+///
+/// # Example
+/// ```rust
+/// use vite_rust::{Tag, TagKind};
+///
+/// let mut tag = Tag::new(TagKind::ModuleScript)
+///     .with_attribute("type", "module")
+///     .with_attribute("src", "/assets/foo.js");
+///
+/// tag.set_attribute("fetchpriority", "high");
+///
+/// assert_eq!(tag.url(), Some("/assets/foo.js"));
+/// assert_eq!(
+///     tag.to_html(),
+///     r#"<script type="module" src="/assets/foo.js" fetchpriority="high"></script>"#
+/// );
+/// ```
+///
+/// [`Vite`]: crate::Vite
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Tag {
+    kind: TagKind,
+    /// Attributes in rendering order. Boolean attributes have no value.
+    attributes: Vec<(String, Option<String>)>,
+    content: Option<String>,
+}
+
+impl Tag {
+    /// Creates a tag of the given kind without attributes nor content.
+    pub fn new(kind: TagKind) -> Self {
+        Tag {
+            kind,
+            attributes: Vec::new(),
+            content: None,
+        }
+    }
+
+    pub fn kind(&self) -> TagKind {
+        self.kind
+    }
+
+    /// Returns the URL the tag references, read from the attribute given by
+    /// [`TagKind::url_attribute`].
+    pub fn url(&self) -> Option<&str> {
+        self.get_attribute(self.kind.url_attribute()?)
+    }
+
+    /// Returns an iterator over the tag's attributes, in rendering order.
+    /// Boolean attributes (e.g. `nomodule`) have no value.
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.attributes
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_deref()))
+    }
+
+    /// Returns the value of the given attribute. Boolean attributes have an
+    /// empty value.
+    pub fn get_attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_deref().unwrap_or(""))
+    }
+
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.attributes
+            .iter()
+            .any(|(attribute, _)| attribute == name)
+    }
+
+    /// Sets the value of an attribute. Existing attributes keep their position,
+    /// new ones are appended to the end.
+    pub fn set_attribute(&mut self, name: &str, value: impl Into<String>) -> &mut Self {
+        self.insert_attribute(name, Some(value.into()));
+        self
+    }
+
+    /// Sets a boolean attribute, such as `nomodule` or `defer`.
+    pub fn set_boolean_attribute(&mut self, name: &str) -> &mut Self {
+        self.insert_attribute(name, None);
+        self
+    }
+
+    pub fn remove_attribute(&mut self, name: &str) -> &mut Self {
+        self.attributes.retain(|(attribute, _)| attribute != name);
+        self
+    }
+
+    pub fn with_attribute(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.set_attribute(name, value);
+        self
+    }
+
+    pub fn with_boolean_attribute(mut self, name: &str) -> Self {
+        self.set_boolean_attribute(name);
+        self
+    }
+
+    /// Returns the tag's inner content, e.g. an inline script.
+    pub fn content(&self) -> Option<&str> {
+        self.content.as_deref()
+    }
+
+    pub fn set_content(&mut self, content: impl Into<String>) -> &mut Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    pub fn with_content(mut self, content: impl Into<String>) -> Self {
+        self.set_content(content);
+        self
+    }
+
+    /// Renders the tag as HTML. `link` tags are self-closed, and `script` tags
+    /// wrap their content, if any.
+    pub fn to_html(&self) -> String {
+        self.to_string()
+    }
+
+    fn insert_attribute(&mut self, name: &str, value: Option<String>) {
+        match self
+            .attributes
+            .iter_mut()
+            .find(|(attribute, _)| attribute == name)
+        {
+            Some((_, current)) => *current = value,
+            None => self.attributes.push((name.to_string(), value)),
+        }
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let element = self.kind.element();
+
+        write!(f, "<{element}")?;
+        for (name, value) in self.attributes() {
+            match value {
+                Some(value) => write!(f, r#" {name}="{}""#, escape_attribute(value))?,
+                None => write!(f, " {name}")?,
+            }
+        }
+
+        match element {
+            "link" => write!(f, " />"),
+            _ => write!(
+                f,
+                ">{}</{element}>",
+                self.content.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// Renders the given tags with [`Tag::to_html`], one per line.
+pub(crate) fn tags_to_html(tags: &[Tag]) -> String {
+    tags.iter()
+        .map(Tag::to_html)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Tag, TagKind};
+
+    #[test]
+    fn test_render_tags() {
+        let mut style_sheet = Tag::new(TagKind::StyleSheet)
+            .with_attribute("rel", "stylesheet")
+            .with_attribute("href", "/assets/foo.css");
+
+        assert_eq!(
+            r#"<link rel="stylesheet" href="/assets/foo.css" />"#,
+            style_sheet.to_html()
+        );
+
+        style_sheet
+            .set_attribute("href", "/assets/bar.css")
+            .set_attribute("data-turbo-track", "reload");
+
+        assert_eq!(
+            r#"<link rel="stylesheet" href="/assets/bar.css" data-turbo-track="reload" />"#,
+            style_sheet.to_html()
+        );
+
+        let legacy_entry = Tag::new(TagKind::LegacyEntry)
+            .with_boolean_attribute("nomodule")
+            .with_attribute("data-src", "/assets/foo-legacy.js?a=1&b=\"2\"")
+            .with_content("System.import()");
+
+        assert_eq!(
+            Some("/assets/foo-legacy.js?a=1&b=\"2\""),
+            legacy_entry.url()
+        );
+        assert_eq!(
+            r#"<script nomodule data-src="/assets/foo-legacy.js?a=1&amp;b=&quot;2&quot;">System.import()</script>"#,
+            legacy_entry.to_html()
+        );
+    }
+
+    #[test]
+    fn test_remove_attribute() {
+        let mut script = Tag::new(TagKind::ModuleScript)
+            .with_attribute("type", "module")
+            .with_attribute("src", "/assets/foo.js")
+            .with_attribute("nonce", "abc");
+
+        script.remove_attribute("nonce");
+
+        assert!(!script.has_attribute("nonce"));
+        assert_eq!(
+            r#"<script type="module" src="/assets/foo.js"></script>"#,
+            script.to_html()
+        );
+    }
+}
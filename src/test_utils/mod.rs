@@ -1,2 +1,4 @@
 mod normalize_strings;
-pub(crate) use normalize_strings::NormalizeHtmlStrings;
\ No newline at end of file
+mod temp_file;
+pub(crate) use normalize_strings::NormalizeHtmlStrings;
+pub(crate) use temp_file::TempFile;
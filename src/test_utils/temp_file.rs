@@ -0,0 +1,37 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// A path in the temporary directory, unique to the test process,
+/// whose file is deleted when dropped.
+pub(crate) struct TempFile(PathBuf);
+
+impl TempFile {
+    pub(crate) fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("vite-rust-test-{}-{name}", process::id()));
+        let _ = fs::remove_file(&path);
+        TempFile(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub(crate) fn to_str(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
@@ -1,31 +1,144 @@
-use std::{future::Future, pin::Pin, time::Duration};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(feature = "async")]
+use std::{future::Future, pin::Pin};
 
+use crate::config::DEFAULT_HEART_BEAT_TIMEOUT;
+#[cfg(feature = "async")]
+use crate::error::ViteError;
+use crate::utils::{DevServerStream, DevServerTls};
 use crate::CLIENT_SCRIPT_PATH;
 
-pub(crate) async fn check_heart_beat(host: &str, timeout: Option<Duration>, retries: u8) -> bool {
-    let timeout = match timeout {
-        Some(t) => t,
-        None => Duration::from_secs(10),
-    };
+/// How the dev-server is probed: each attempt's timeout, and the delays
+/// between attempts, which grow exponentially up to a total deadline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HeartBeatOptions {
+    /// Connect and read timeout of each attempt. Defaults to
+    /// [`DEFAULT_HEART_BEAT_TIMEOUT`].
+    pub timeout: Option<Duration>,
+    /// How many attempts are made after the first one fails.
+    pub retries: u8,
+    /// Delay before the first retry. Retries happen right away if `None`.
+    pub initial_delay: Option<Duration>,
+    /// By how much the delay is multiplied after each retry.
+    pub backoff_factor: u32,
+    /// Whether each delay is randomly shortened by up to a half, so that
+    /// several processes don't probe the dev-server in lockstep.
+    pub jitter: bool,
+    /// How long probing may take overall, retries included.
+    pub deadline: Option<Duration>,
+    /// The certificates trusted if the dev-server is served over `https`.
+    pub tls: Arc<DevServerTls>,
+}
+
+/// The schedule of the attempts made under some [`HeartBeatOptions`].
+struct Backoff<'a> {
+    options: &'a HeartBeatOptions,
+    started_at: Instant,
+    attempts: u8,
+    delay: Option<Duration>,
+}
+
+impl<'a> Backoff<'a> {
+    fn new(options: &'a HeartBeatOptions) -> Self {
+        Backoff {
+            options,
+            started_at: Instant::now(),
+            attempts: 0,
+            delay: options.initial_delay,
+        }
+    }
 
-    let ping_endpoint = match host.ends_with("/") {
-        true => {
-            let mut host = host.to_string();
-            host.push_str(CLIENT_SCRIPT_PATH);
-            host.into_boxed_str()
+    /// Starts a new attempt, returning its timeout, which never goes past the
+    /// deadline, or `None` if the deadline is already over.
+    fn attempt(&mut self) -> Option<Duration> {
+        let timeout = self.options.timeout.unwrap_or(DEFAULT_HEART_BEAT_TIMEOUT);
+        let timeout = match self.remaining() {
+            Some(remaining) => timeout.min(remaining),
+            None => timeout,
+        };
+
+        if timeout.is_zero() {
+            return None;
         }
-        false => {
-            let mut host = host.to_string();
-            host.push('/');
-            host.push_str(CLIENT_SCRIPT_PATH);
-            host.into_boxed_str()
+
+        self.attempts += 1;
+        Some(timeout)
+    }
+
+    /// Returns how long to wait before the next attempt, or `None` if there
+    /// should be no more attempts.
+    fn next_delay(&mut self) -> Option<Duration> {
+        if self.attempts > self.options.retries {
+            return None;
         }
-    };
 
-    let response = retry_cb(retries, move || {
-        let ping_endpoint = ping_endpoint.clone().to_string();
+        let delay = match self.delay {
+            Some(delay) => {
+                self.delay = Some(delay.saturating_mul(self.options.backoff_factor));
+                match self.options.jitter {
+                    true => jitter(delay),
+                    false => delay,
+                }
+            }
+            None => Duration::ZERO,
+        };
+
+        match self.remaining() {
+            Some(remaining) if remaining <= delay => None,
+            _ => Some(delay),
+        }
+    }
+
+    fn remaining(&self) -> Option<Duration> {
+        self.options
+            .deadline
+            .map(|deadline| deadline.saturating_sub(self.started_at.elapsed()))
+    }
+
+    fn log_outcome(&self, host: &str, is_up: bool) {
+        log::info!(
+            "Vite development server at {host} is {} after {} attempt(s) in {:?}.",
+            if is_up { "up" } else { "down" },
+            self.attempts,
+            self.started_at.elapsed()
+        );
+    }
+}
+
+/// Randomly shortens the delay by up to a half.
+fn jitter(delay: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    let per_mille = (random % 1000) as u32;
+
+    delay / 2 + delay / 2 * per_mille / 1000
+}
+
+/// Checks whether the dev-server at `host` is running, retrying as told by `options`.
+///
+/// # Errors
+/// Returns an `InvalidConfig` error if no HTTP client can be set up with the
+/// configured certificates.
+#[cfg(feature = "async")]
+pub(crate) async fn check_heart_beat(
+    host: &str,
+    options: &HeartBeatOptions,
+) -> Result<bool, ViteError> {
+    let ping_endpoint = get_ping_endpoint(host);
+    let client = options
+        .tls
+        .reqwest_client(host, reqwest::redirect::Policy::default())?;
+    let mut backoff = Backoff::new(options);
+
+    let response = retry_cb(&mut backoff, |timeout| {
+        let ping_endpoint = ping_endpoint.clone();
+        let client = client.clone();
         Box::pin(async move {
-            reqwest::Client::new()
+            client
                 .get(ping_endpoint.as_str())
                 .timeout(timeout)
                 .send()
@@ -34,24 +147,151 @@ pub(crate) async fn check_heart_beat(host: &str, timeout: Option<Duration>, retr
     })
     .await;
 
-    match response {
+    let is_up = match response {
+        None => {
+            log::warn!("Vite development server handshake has run out of time.");
+            false
+        }
+        Some(Err(err)) => {
+            log::warn!("Vite development server handshake has failed: {}.", err);
+            false
+        }
+        Some(Ok(response)) => response.status() == 200,
+    };
+
+    backoff.log_outcome(host, is_up);
+    Ok(is_up)
+}
+
+/// Does the same as [`check_heart_beat`], with a plain HTTP/1.1 request over a
+/// blocking connection. The timeout applies to connecting, writing and reading.
+///
+/// Without the `tls` feature, a `https` dev-server can't be reached, and is
+/// considered down without retrying.
+pub(crate) fn check_heart_beat_blocking(host: &str, options: &HeartBeatOptions) -> bool {
+    let ping_endpoint = get_ping_endpoint(host);
+    let mut backoff = Backoff::new(options);
+
+    let mut response = Err(io::Error::new(io::ErrorKind::TimedOut, "ran out of time"));
+    while let Some(timeout) = backoff.attempt() {
+        response = ping_blocking(&ping_endpoint, timeout, &options.tls);
+        match &response {
+            Ok(_) => break,
+            Err(err) if err.kind() == io::ErrorKind::Unsupported => break,
+            Err(_) => {}
+        }
+
+        match backoff.next_delay() {
+            Some(delay) => thread::sleep(delay),
+            None => break,
+        }
+    }
+
+    let is_up = match response {
         Err(err) => {
             log::warn!("Vite development server handshake has failed: {}.", err);
             false
         }
-        Ok(response) => response.status() == 200,
+        Ok(status) => status == 200,
+    };
+
+    backoff.log_outcome(host, is_up);
+    is_up
+}
+
+/// Pings the dev-server once, without retrying nor logging, as the background
+/// monitor does on each tick.
+pub(crate) fn is_dev_server_up(host: &str, timeout: Option<Duration>, tls: &DevServerTls) -> bool {
+    let ping_endpoint = get_ping_endpoint(host);
+    matches!(
+        ping_blocking(
+            &ping_endpoint,
+            timeout.unwrap_or(DEFAULT_HEART_BEAT_TIMEOUT),
+            tls
+        ),
+        Ok(200)
+    )
+}
+
+/// Requests the given URL, returning the response's status code.
+fn ping_blocking(url: &str, timeout: Duration, tls: &DevServerTls) -> io::Result<u16> {
+    let (_, authority, _, path) = split_url(url)?;
+
+    let request = format!(
+        "GET {path} HTTP/1.1\r\nHost: {authority}\r\nAccept: */*\r\nConnection: close\r\n\r\n"
+    );
+
+    let mut stream = DevServerStream::connect(url, timeout, tls)?;
+    stream.write_all(request.as_bytes())?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected response from {url}: {}", status_line.trim()),
+            )
+        })
+}
+
+/// Splits an `http(s)` URL into its scheme, authority, `host:port` address
+/// (with the scheme's default port, if missing) and path.
+pub(crate) fn split_url(url: &str) -> io::Result<(&str, &str, String, &str)> {
+    let invalid_url = || io::Error::new(io::ErrorKind::InvalidInput, format!("invalid URL {url}"));
+
+    let (scheme, rest) = url.split_once("://").ok_or_else(invalid_url)?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+
+    let has_port = match authority.rfind(']') {
+        Some(bracket) => authority[bracket..].contains(':'),
+        None => authority.contains(':'),
+    };
+    let address = match (has_port, scheme) {
+        (true, _) => authority.to_string(),
+        (false, "https") => format!("{authority}:443"),
+        (false, "http") => format!("{authority}:80"),
+        _ => return Err(invalid_url()),
+    };
+
+    Ok((scheme, authority, address, path))
+}
+
+fn get_ping_endpoint(host: &str) -> String {
+    match host.ends_with("/") {
+        true => format!("{host}{CLIENT_SCRIPT_PATH}"),
+        false => format!("{host}/{CLIENT_SCRIPT_PATH}"),
     }
 }
 
+/// Calls `cb` with each attempt's timeout until it succeeds or `backoff` runs
+/// out of attempts, returning the last response, or `None` if the deadline was
+/// over before the first attempt.
+#[cfg(feature = "async")]
 async fn retry_cb<T, E>(
-    mut retries_count: u8,
-    cb: impl Send + Sync + 'static + Fn() -> Pin<Box<dyn Future<Output = Result<T, E>>>> + 'static,
-) -> Result<T, E> {
-    let mut response = cb().await;
+    backoff: &mut Backoff<'_>,
+    cb: impl Fn(Duration) -> Pin<Box<dyn Future<Output = Result<T, E>>>>,
+) -> Option<Result<T, E>> {
+    let mut response = None;
+    while let Some(timeout) = backoff.attempt() {
+        let result = cb(timeout).await;
+        let is_ok = result.is_ok();
+        response = Some(result);
+        if is_ok {
+            break;
+        }
 
-    while response.is_err() && retries_count > 0 {
-        response = cb().await;
-        retries_count -= 1;
+        match backoff.next_delay() {
+            Some(delay) => tokio::time::sleep(delay).await,
+            None => break,
+        }
     }
 
     response
@@ -59,15 +299,249 @@ async fn retry_cb<T, E>(
 
 #[cfg(test)]
 mod test {
-    use super::retry_cb;
-    use std::sync::{Arc, Mutex};
+    use super::{check_heart_beat_blocking, Backoff, HeartBeatOptions};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    fn options(retries: u8) -> HeartBeatOptions {
+        HeartBeatOptions {
+            timeout: Some(Duration::from_secs(1)),
+            retries,
+            initial_delay: None,
+            backoff_factor: 2,
+            jitter: false,
+            deadline: None,
+            tls: Default::default(),
+        }
+    }
+
+    /// Serves a single request with the given status line, returning the server's URL.
+    fn serve_once(status_line: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            assert!(request.starts_with(b"GET /@vite/client HTTP/1.1\r\n"));
+
+            write!(stream, "{status_line}\r\nContent-Length: 0\r\n\r\n").unwrap();
+        });
+
+        url
+    }
+
+    #[test]
+    fn test_check_heart_beat_blocking() {
+        assert!(check_heart_beat_blocking(
+            &serve_once("HTTP/1.1 200 OK"),
+            &options(0)
+        ));
+        assert!(!check_heart_beat_blocking(
+            &serve_once("HTTP/1.1 404 Not Found"),
+            &options(0)
+        ));
+
+        let unused_port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        assert!(!check_heart_beat_blocking(
+            &format!("http://127.0.0.1:{unused_port}/"),
+            &options(1)
+        ));
+    }
+
+    /// Serves `200 OK` over TLS with the `tests/tls/localhost.pem` self-signed
+    /// certificate, returning the server's URL.
+    #[cfg(feature = "tls")]
+    fn serve_https() -> String {
+        let identity = native_tls::Identity::from_pkcs8(
+            include_bytes!("../../tests/tls/localhost.pem"),
+            include_bytes!("../../tests/tls/localhost-key.pem"),
+        )
+        .unwrap();
+        let acceptor = native_tls::TlsAcceptor::new(identity).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "https://localhost:{}",
+            listener.local_addr().unwrap().port()
+        );
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = acceptor.accept(stream.unwrap()) else {
+                    continue;
+                };
+
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request);
+                let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+            }
+        });
+
+        url
+    }
 
+    #[cfg(feature = "tls")]
+    #[test]
+    fn test_check_heart_beat_blocking_https() {
+        use super::DevServerTls;
+        use crate::ViteConfig;
+        use std::sync::Arc;
+
+        let url = serve_https();
+        let options_with = |config: ViteConfig| HeartBeatOptions {
+            tls: Arc::new(DevServerTls::load(&config).unwrap()),
+            ..options(0)
+        };
+
+        assert!(!check_heart_beat_blocking(&url, &options(0)));
+        assert!(check_heart_beat_blocking(
+            &url,
+            &options_with(ViteConfig::default().set_dev_server_ca_path("tests/tls/localhost.pem"))
+        ));
+        assert!(check_heart_beat_blocking(
+            &url,
+            &options_with(
+                ViteConfig::default().set_pinned_dev_server_cert_path("tests/tls/localhost.pem")
+            )
+        ));
+        assert!(!check_heart_beat_blocking(
+            &url,
+            &options_with(
+                ViteConfig::default().set_pinned_dev_server_cert_path("tests/tls/other.pem")
+            )
+        ));
+        assert!(check_heart_beat_blocking(
+            &url,
+            &options_with(ViteConfig::default().set_accept_invalid_dev_server_certs(true))
+        ));
+    }
+
+    #[cfg(all(feature = "async", feature = "tls"))]
+    #[tokio::test]
+    async fn test_check_heart_beat_https() {
+        use super::{check_heart_beat, DevServerTls};
+        use crate::ViteConfig;
+        use std::sync::Arc;
+
+        let url = serve_https();
+        let options_with = |config: ViteConfig| HeartBeatOptions {
+            tls: Arc::new(DevServerTls::load(&config).unwrap()),
+            ..options(0)
+        };
+
+        assert!(!check_heart_beat(&url, &options(0)).await.unwrap());
+        assert!(check_heart_beat(
+            &url,
+            &options_with(
+                ViteConfig::default().set_pinned_dev_server_cert_path("tests/tls/localhost.pem")
+            )
+        )
+        .await
+        .unwrap());
+        assert!(!check_heart_beat(
+            &url,
+            &options_with(
+                ViteConfig::default().set_pinned_dev_server_cert_path("tests/tls/other.pem")
+            )
+        )
+        .await
+        .unwrap());
+        assert!(check_heart_beat(
+            &url,
+            &options_with(ViteConfig::default().set_accept_invalid_dev_server_certs(true))
+        )
+        .await
+        .unwrap());
+    }
+
+    #[cfg(not(feature = "tls"))]
+    #[test]
+    fn test_check_heart_beat_blocking_https_without_tls() {
+        use super::ping_blocking;
+        use std::io;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("https://{}", listener.local_addr().unwrap());
+
+        let error = ping_blocking(&url, Duration::from_secs(1), &Default::default()).unwrap_err();
+        assert_eq!(io::ErrorKind::Unsupported, error.kind());
+        assert!(!check_heart_beat_blocking(&url, &options(3)));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let options = HeartBeatOptions {
+            initial_delay: Some(Duration::from_millis(100)),
+            ..options(3)
+        };
+
+        let mut backoff = Backoff::new(&options);
+        let mut delays = Vec::new();
+        loop {
+            backoff.attempt();
+            match backoff.next_delay() {
+                Some(delay) => delays.push(delay),
+                None => break,
+            }
+        }
+
+        assert_eq!(
+            vec![
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                Duration::from_millis(400)
+            ],
+            delays
+        );
+
+        let options = HeartBeatOptions {
+            jitter: true,
+            deadline: Some(Duration::from_millis(250)),
+            ..options
+        };
+
+        let mut backoff = Backoff::new(&options);
+        let timeout = backoff.attempt().unwrap();
+        assert!(timeout > Duration::from_millis(200) && timeout <= Duration::from_millis(250));
+
+        let delay = backoff.next_delay().unwrap();
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+
+        backoff.attempt();
+        assert!(backoff.next_delay().unwrap() <= Duration::from_millis(200));
+
+        thread::sleep(Duration::from_millis(250));
+        assert_eq!(None, backoff.attempt());
+        assert_eq!(None, backoff.next_delay());
+
+        let options = HeartBeatOptions {
+            deadline: Some(Duration::ZERO),
+            ..options
+        };
+        assert_eq!(None, Backoff::new(&options).attempt());
+        assert!(!check_heart_beat_blocking("http://127.0.0.1:1", &options));
+    }
+
+    #[cfg(feature = "async")]
     #[tokio::test]
     async fn test_retry_cb() {
+        use super::retry_cb;
+        use std::sync::{Arc, Mutex};
+
+        let options = options(2);
+
         let tries_count = Arc::new(Mutex::new(0));
         let tries_clone = Arc::clone(&tries_count);
 
-        let success_on_last_retry = retry_cb(2, move || {
+        let success_on_last_retry = retry_cb(&mut Backoff::new(&options), move |_| {
             let tries_count = tries_clone.clone();
             Box::pin(async move {
                 let count = *tries_count.lock().unwrap();
@@ -83,7 +557,7 @@ mod test {
 
         let tries_count = Arc::new(Mutex::new(0));
         let tries_clone = Arc::clone(&tries_count);
-        let total_failure = retry_cb(2, move || {
+        let total_failure = retry_cb(&mut Backoff::new(&options), move |_| {
             let tries_count = tries_clone.clone();
             Box::pin(async move {
                 let count = *tries_count.lock().unwrap();
@@ -97,7 +571,7 @@ mod test {
         })
         .await;
 
-        assert!(success_on_last_retry.is_ok());
-        assert!(total_failure.is_err());
+        assert!(success_on_last_retry.unwrap().is_ok());
+        assert!(total_failure.unwrap().is_err());
     }
 }
@@ -0,0 +1,58 @@
+use std::fs;
+use std::io::ErrorKind;
+
+use crate::error::{ViteError, ViteErrorKind};
+
+/// Reads the dev-server URL from the hot file written by the Vite plugin.
+///
+/// Returns `None` if the file doesn't exist, meaning the dev-server isn't running.
+pub(crate) fn read_hot_file(path: &str) -> Result<Option<String>, ViteError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(ViteError::from_io(
+                format!("Failed to read hot file at {path}: {err}"),
+                path,
+                err,
+            ))
+        }
+    };
+
+    let url = content.trim();
+    let url = url.strip_suffix("/").unwrap_or(url);
+
+    if url.is_empty() {
+        return Err(ViteError::new(
+            format!("Hot file at {path} is empty."),
+            ViteErrorKind::InvalidConfig,
+        ));
+    }
+
+    log::debug!("Found Vite dev-server URL {url} in hot file {path}.");
+    Ok(Some(url.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::read_hot_file;
+    use crate::test_utils::TempFile;
+    use std::fs;
+
+    #[test]
+    fn test_read_hot_file() {
+        let hot_file = TempFile::new("read-hot-file");
+        let hot_file_path = hot_file.to_str();
+
+        assert_eq!(None, read_hot_file(hot_file_path).unwrap());
+
+        fs::write(&hot_file, "http://localhost:5174/\n").unwrap();
+        assert_eq!(
+            Some("http://localhost:5174".to_string()),
+            read_hot_file(hot_file_path).unwrap()
+        );
+
+        fs::write(&hot_file, "").unwrap();
+        assert!(read_hot_file(hot_file_path).is_err());
+    }
+}
@@ -1,5 +1,14 @@
 mod heart_beat;
+mod hot_file;
 mod resolve_path;
+mod tls;
 
+#[cfg(feature = "async")]
 pub(crate) use heart_beat::check_heart_beat;
+pub(crate) use heart_beat::check_heart_beat_blocking;
+pub(crate) use heart_beat::is_dev_server_up;
+pub(crate) use heart_beat::split_url;
+pub(crate) use heart_beat::HeartBeatOptions;
+pub(crate) use hot_file::read_hot_file;
 pub use resolve_path::resolve_path;
+pub(crate) use tls::{host_name, is_local_host, DevServerStream, DevServerTls};
@@ -1,22 +1,26 @@
 use std::env::current_dir;
 use std::path::{Component, Path, PathBuf};
 
+use crate::error::{ViteError, ViteErrorKind};
+
 /// Experimental utility for resolving the manifest path.
 ///
 /// # Arguments
 /// * `file`    - the current script file path (obtained with `file!()` macro);
 /// * `path`    - the "path/to/manifest.json" string slice.
 ///
-/// # Panics
-/// This function might panic in various occasions:
+/// # Errors
+/// Returns a `ViteError` in various occasions:
 /// - if `file` has no parent directory (e.g. "src/main.rs" parent would be "src");
 /// - if it fails to find the `file` first segment somehow (e.g. "path/to/file" => "path");
 /// - if the first file path [`Component`] is neither `RootDir` nor `Normal`;
+/// - if the current working directory can't be read;
 /// - if final result fails to be canonicalized.
 ///
 /// The last situation might occur if the final path doesn't really lead to
-/// any existing file/directory. Or perhaps, because this function has a bug!
-/// In this case, please open an issue at our GitHub repository!
+/// any existing file/directory, which gives a `MissingFile` error. Or perhaps,
+/// because this function has a bug! In this case, please open an issue at our
+/// GitHub repository!
 ///
 /// # Example
 /// ```plaintext
@@ -30,7 +34,7 @@ use std::path::{Component, Path, PathBuf};
 /// ```ignore
 ///
 /// // example/src/main.rs
-/// let manifest_path = resolve_path(file!(), "../dist/.vite/manifest.json");
+/// let manifest_path = resolve_path(file!(), "../dist/.vite/manifest.json")?;
 /// let mut vite_config = ViteConfig::new_with_defaults(&manifest_path);
 ///
 /// println!("{manifest_path}");
@@ -38,39 +42,67 @@ use std::path::{Component, Path, PathBuf};
 /// ```
 ///
 /// [`Component`]: std::path::Component
-pub fn resolve_path(file: &str, path: &str) -> String {
+pub fn resolve_path(file: &str, path: &str) -> Result<String, ViteError> {
     let path: &Path = std::path::Path::new(path);
-    let mut this_file_directory = Path::new(file)
-        .parent()
-        .expect("Could not get current file's directory.")
-        .to_path_buf();
+    let mut this_file_directory = match Path::new(file).parent() {
+        Some(directory) => directory.to_path_buf(),
+        None => {
+            return Err(ViteError::new(
+                format!("Could not get {file}'s directory."),
+                ViteErrorKind::InvalidConfig,
+            ))
+        }
+    };
 
     #[allow(unused_mut)]
     let mut fp_fs: String; // file path first segment
     match this_file_directory.components().next() {
         Some(Component::Normal(segment)) => fp_fs = segment.to_string_lossy().to_string(),
         Some(Component::RootDir) => {
-            let component = this_file_directory.components().next().unwrap_or_else(|| {
-                panic!(
-                    "Failed to find first directory segment from path {}.",
-                    this_file_directory.to_string_lossy()
-                )
-            });
+            let component = match this_file_directory.components().next() {
+                Some(component) => component,
+                None => {
+                    return Err(ViteError::new(
+                        format!(
+                            "Failed to find first directory segment from path {}.",
+                            this_file_directory.to_string_lossy()
+                        ),
+                        ViteErrorKind::InvalidConfig,
+                    ))
+                }
+            };
 
             match component {
                 Component::Normal(segment) => fp_fs = segment.to_string_lossy().to_string(),
                 _ => {
-                    panic!(
-                        "Failed to find first directory normal segment from path {}.",
-                        this_file_directory.to_string_lossy()
-                    )
+                    return Err(ViteError::new(
+                        format!(
+                            "Failed to find first directory normal segment from path {}.",
+                            this_file_directory.to_string_lossy()
+                        ),
+                        ViteErrorKind::InvalidConfig,
+                    ))
                 }
             }
         }
-        _ => panic!("Unexpected kind of directory."),
+        _ => {
+            return Err(ViteError::new(
+                "Unexpected kind of directory.",
+                ViteErrorKind::InvalidConfig,
+            ))
+        }
     }
 
-    let curr_dir = current_dir().unwrap();
+    let curr_dir = match current_dir() {
+        Ok(curr_dir) => curr_dir,
+        Err(err) => {
+            return Err(ViteError::with_source(
+                format!("Failed to get the current directory: {err}"),
+                ViteErrorKind::Io,
+                err,
+            ))
+        }
+    };
 
     let paths_are_redundant = curr_dir.ends_with(&fp_fs) && this_file_directory.starts_with(&fp_fs);
 
@@ -88,10 +120,13 @@ pub fn resolve_path(file: &str, path: &str) -> String {
     }
 
     let joined_path = this_file_directory.join(path);
-    let canonicalized = joined_path.canonicalize();
-    match canonicalized {
-        Err(err) => panic!("{}\n{}\n", err, joined_path.to_string_lossy()),
-        Ok(path) => path.to_string_lossy().to_string(),
+    match joined_path.canonicalize() {
+        Err(err) => Err(ViteError::from_io(
+            format!("Failed to resolve {}: {err}", joined_path.to_string_lossy()),
+            joined_path,
+            err,
+        )),
+        Ok(path) => Ok(path.to_string_lossy().to_string()),
     }
 }
 
@@ -103,7 +138,7 @@ mod test {
     fn test_resolve_path() {
         let abs_path = "tests/dummy.txt";
         let rel_path = "../../tests/dummy.txt";
-        let resolved_rel_path = super::resolve_path(file!(), rel_path);
+        let resolved_rel_path = super::resolve_path(file!(), rel_path).unwrap();
 
         let mut abs_file_contents = String::new();
         let mut rel_file_contents = String::new();
@@ -117,4 +152,13 @@ mod test {
 
         assert_eq!(abs_file_contents, rel_file_contents);
     }
+
+    #[test]
+    fn test_resolve_unexisting_path() {
+        let error = super::resolve_path(file!(), "../../tests/unexisting.txt").unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            crate::error::ViteErrorKind::MissingFile { .. }
+        ));
+    }
 }
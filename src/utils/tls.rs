@@ -0,0 +1,382 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::error::{ViteError, ViteErrorKind};
+use crate::utils::split_url;
+use crate::ViteConfig;
+
+/// The certificates trusted when connecting to a `https` dev-server, as set by
+/// the [`ViteConfig`] TLS options.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct DevServerTls {
+    /// A PEM-encoded CA certificate, trusted along with the system's ones.
+    ca: Option<Vec<u8>>,
+    /// A PEM-encoded certificate, trusted instead of the system's ones.
+    pinned: Option<Vec<u8>>,
+    /// Whether invalid certificates are accepted from a localhost dev-server.
+    accept_invalid: bool,
+}
+
+impl DevServerTls {
+    /// Reads the certificates set in the config.
+    ///
+    /// # Errors
+    /// Returns a `MissingFile` or `Io` error if a certificate can't be read, and an
+    /// `InvalidConfig` one if it isn't a PEM-encoded certificate.
+    pub(crate) fn load(config: &ViteConfig) -> Result<Self, ViteError> {
+        Ok(DevServerTls {
+            ca: read_certificate(config.dev_server_ca_path.as_deref(), "CA certificate")?,
+            pinned: read_certificate(
+                config.pinned_dev_server_cert_path.as_deref(),
+                "pinned certificate",
+            )?,
+            accept_invalid: config.accept_invalid_dev_server_certs,
+        })
+    }
+
+    /// Checks whether invalid certificates may be accepted from `host`, which is
+    /// only ever the case for the local machine.
+    #[cfg(any(feature = "async", feature = "tls"))]
+    fn accepts_invalid_certs(&self, host: &str) -> bool {
+        if !self.accept_invalid {
+            return false;
+        }
+
+        let is_local = is_local_host(host);
+        if !is_local {
+            log::debug!("Not accepting invalid certificates from {host}, which is not localhost.");
+        }
+
+        is_local
+    }
+
+    /// Returns a client trusting the dev-server at `url` as configured, and following
+    /// redirects as told by `redirect`.
+    ///
+    /// # Errors
+    /// Returns an `InvalidConfig` error if the certificates can't be used by the
+    /// client.
+    #[cfg(feature = "async")]
+    pub(crate) fn reqwest_client(
+        &self,
+        url: &str,
+        redirect: reqwest::redirect::Policy,
+    ) -> Result<reqwest::Client, ViteError> {
+        let mut builder = reqwest::Client::builder().redirect(redirect);
+
+        if let Some(ca) = &self.ca {
+            builder = builder.add_root_certificate(reqwest_certificate(ca, "CA certificate")?);
+        }
+
+        if let Some(pinned) = &self.pinned {
+            builder = builder
+                .tls_built_in_root_certs(false)
+                .add_root_certificate(reqwest_certificate(pinned, "pinned certificate")?);
+        }
+
+        let host = split_url(url)
+            .map(|(_, authority, _, _)| host_name(authority).to_string())
+            .unwrap_or_default();
+
+        builder
+            .danger_accept_invalid_certs(self.accepts_invalid_certs(&host))
+            .build()
+            .map_err(|err| {
+                ViteError::with_source(
+                    format!("Failed to set up the HTTP client for the dev-server at {url}: {err}"),
+                    ViteErrorKind::InvalidConfig,
+                    err,
+                )
+            })
+    }
+
+    /// Returns a TLS connector trusting the dev-server at `host` as configured.
+    #[cfg(feature = "tls")]
+    pub(crate) fn connector(&self, host: &str) -> io::Result<native_tls::TlsConnector> {
+        let mut builder = native_tls::TlsConnector::builder();
+
+        if let Some(ca) = &self.ca {
+            builder.add_root_certificate(
+                native_tls::Certificate::from_pem(ca).map_err(io::Error::other)?,
+            );
+        }
+
+        if let Some(pinned) = &self.pinned {
+            builder.disable_built_in_roots(true).add_root_certificate(
+                native_tls::Certificate::from_pem(pinned).map_err(io::Error::other)?,
+            );
+        }
+
+        builder
+            .danger_accept_invalid_certs(self.accepts_invalid_certs(host))
+            .build()
+            .map_err(io::Error::other)
+    }
+}
+
+/// A blocking connection to the dev-server, over TLS if it is served over `https`.
+#[derive(Debug)]
+pub(crate) enum DevServerStream {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<native_tls::TlsStream<TcpStream>>),
+}
+
+impl DevServerStream {
+    /// Connects to the dev-server at `url`. The timeout applies to connecting,
+    /// writing and reading.
+    #[cfg_attr(not(feature = "tls"), allow(unused_variables))]
+    pub(crate) fn connect(url: &str, timeout: Duration, tls: &DevServerTls) -> io::Result<Self> {
+        let (scheme, authority, address, _) = split_url(url)?;
+        let stream = connect_tcp(url, &address, timeout)?;
+
+        match scheme {
+            #[cfg(feature = "tls")]
+            "https" => {
+                let host = host_name(authority);
+                let stream =
+                    tls.connector(host)?
+                        .connect(host, stream)
+                        .map_err(|err| match err {
+                            native_tls::HandshakeError::Failure(err) => {
+                                io::Error::new(io::ErrorKind::InvalidData, err)
+                            }
+                            native_tls::HandshakeError::WouldBlock(_) => io::Error::new(
+                                io::ErrorKind::TimedOut,
+                                "the TLS handshake timed out",
+                            ),
+                        })?;
+
+                Ok(DevServerStream::Tls(Box::new(stream)))
+            }
+            #[cfg(not(feature = "tls"))]
+            "https" => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("can't connect to {url} without the `tls` feature"),
+            )),
+            _ => Ok(DevServerStream::Plain(stream)),
+        }
+    }
+
+    /// Returns the underlying TCP connection, e.g. to change its timeouts.
+    #[cfg(feature = "hmr")]
+    pub(crate) fn tcp(&self) -> &TcpStream {
+        match self {
+            DevServerStream::Plain(stream) => stream,
+            #[cfg(feature = "tls")]
+            DevServerStream::Tls(stream) => stream.get_ref(),
+        }
+    }
+}
+
+impl Read for DevServerStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            DevServerStream::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            DevServerStream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for DevServerStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            DevServerStream::Plain(stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            DevServerStream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            DevServerStream::Plain(stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            DevServerStream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// Opens a TCP connection to the first reachable socket address of `address`.
+fn connect_tcp(url: &str, address: &str, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(
+        io::ErrorKind::AddrNotAvailable,
+        format!("{url} resolves to no address"),
+    );
+
+    for socket_address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_address, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                return Ok(stream);
+            }
+            Err(err) => last_error = err,
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Strips the port, and the brackets of IPv6 addresses, from a URL authority.
+pub(crate) fn host_name(authority: &str) -> &str {
+    match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(rest),
+        None => authority.split(':').next().unwrap_or(authority),
+    }
+}
+
+/// Checks whether `host` is the local machine.
+pub(crate) fn is_local_host(host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+
+    host == "localhost"
+        || host.ends_with(".localhost")
+        || host
+            .parse::<IpAddr>()
+            .is_ok_and(|address| address.is_loopback())
+}
+
+fn read_certificate(path: Option<&str>, name: &str) -> Result<Option<Vec<u8>>, ViteError> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    let pem = fs::read(path).map_err(|err| {
+        ViteError::from_io(
+            format!("Failed to read the dev-server's {name} at {path}: {err}"),
+            path,
+            err,
+        )
+    })?;
+
+    check_certificate(&pem).map_err(|err| {
+        ViteError::with_source(
+            format!("The dev-server's {name} at {path} is not a PEM-encoded certificate: {err}"),
+            ViteErrorKind::InvalidConfig,
+            err,
+        )
+    })?;
+
+    Ok(Some(pem))
+}
+
+#[cfg_attr(not(any(feature = "async", feature = "tls")), allow(unused_variables))]
+fn check_certificate(pem: &[u8]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    #[cfg(feature = "tls")]
+    native_tls::Certificate::from_pem(pem)?;
+    #[cfg(feature = "async")]
+    reqwest::Certificate::from_pem(pem)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+fn reqwest_certificate(pem: &[u8], name: &str) -> Result<reqwest::Certificate, ViteError> {
+    reqwest::Certificate::from_pem(pem).map_err(|err| {
+        ViteError::with_source(
+            format!("The dev-server's {name} is not a PEM-encoded certificate: {err}"),
+            ViteErrorKind::InvalidConfig,
+            err,
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{host_name, is_local_host, DevServerTls};
+    use crate::error::ViteErrorKind;
+    use crate::ViteConfig;
+
+    #[test]
+    fn test_local_hosts() {
+        assert_eq!("localhost", host_name("localhost:5173"));
+        assert_eq!("::1", host_name("[::1]:5173"));
+        assert_eq!("example.com", host_name("example.com"));
+
+        for host in [
+            "localhost",
+            "LOCALHOST",
+            "app.localhost",
+            "127.0.0.1",
+            "127.1.2.3",
+            "::1",
+        ] {
+            assert!(is_local_host(host), "{host} should be local");
+        }
+        for host in [
+            "example.com",
+            "localhost.example.com",
+            "192.168.0.10",
+            "0.0.0.0",
+        ] {
+            assert!(!is_local_host(host), "{host} should not be local");
+        }
+
+        #[cfg(any(feature = "async", feature = "tls"))]
+        {
+            let tls = DevServerTls {
+                accept_invalid: true,
+                ..Default::default()
+            };
+            assert!(tls.accepts_invalid_certs("localhost"));
+            assert!(!tls.accepts_invalid_certs("dev.example.com"));
+            assert!(!DevServerTls::default().accepts_invalid_certs("localhost"));
+        }
+    }
+
+    #[test]
+    fn test_load() {
+        let tls = DevServerTls::load(
+            &ViteConfig::default()
+                .set_dev_server_ca_path("tests/tls/localhost.pem")
+                .set_accept_invalid_dev_server_certs(true),
+        )
+        .unwrap();
+        assert!(tls.ca.is_some());
+        assert!(tls.pinned.is_none());
+        assert!(tls.accept_invalid);
+
+        let error = DevServerTls::load(
+            &ViteConfig::default().set_pinned_dev_server_cert_path("tests/tls/missing.pem"),
+        )
+        .unwrap_err();
+        assert!(matches!(error.kind(), ViteErrorKind::MissingFile { .. }));
+
+        // certificates can only be parsed with a TLS backend around
+        #[cfg(any(feature = "async", feature = "tls"))]
+        {
+            let error = DevServerTls::load(
+                &ViteConfig::default().set_dev_server_ca_path("tests/tls/invalid.pem"),
+            )
+            .unwrap_err();
+            assert_eq!(&ViteErrorKind::InvalidConfig, error.kind());
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_reqwest_client() {
+        use reqwest::redirect::Policy;
+
+        let tls = DevServerTls::load(
+            &ViteConfig::default().set_pinned_dev_server_cert_path("tests/tls/localhost.pem"),
+        )
+        .unwrap();
+        assert!(tls
+            .reqwest_client("https://localhost:5173", Policy::none())
+            .is_ok());
+
+        let tls = DevServerTls {
+            ca: Some(b"not a certificate".to_vec()),
+            ..Default::default()
+        };
+        let error = tls
+            .reqwest_client("https://localhost:5173", Policy::none())
+            .unwrap_err();
+        assert_eq!(&ViteErrorKind::InvalidConfig, error.kind());
+    }
+}
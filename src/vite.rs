@@ -1,31 +1,59 @@
 use std::env;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, RwLock, RwLockReadGuard};
+use std::time::Duration;
 
-use crate::asset::Asset;
+#[cfg(feature = "hmr")]
+use serde::Serialize;
+
+use crate::asset::{is_style_sheet, with_security_attributes, Asset};
 use crate::config::{ViteConfig, ViteMode};
 use crate::error::{ViteError, ViteErrorKind};
-use crate::manifest::Manifest;
-use crate::CLIENT_SCRIPT_PATH;
+#[cfg(feature = "hmr")]
+use crate::hmr::{send_custom_event, HmrError, HmrListener};
+use crate::manifest::{ManifestWatcher, ReloadableManifest, TagsOptions};
+use crate::monitor::DevServerMonitor;
+#[cfg(feature = "proxy")]
+use crate::proxy::DevServerProxy;
+use crate::tag::{tags_to_html, Tag, TagKind};
+use crate::utils::{read_hot_file, DevServerTls};
+use crate::{BACKEND_ERROR_EVENT, BACKEND_RELOAD_EVENT, CLIENT_SCRIPT_PATH};
 
-pub(crate) type Entrypoints = Vec<Box<str>>;
+pub(crate) type Entrypoints = Arc<[Arc<str>]>;
 
-#[derive(Debug)]
+/// The Vite integration, resolving tags and URLs from either the dev-server or the
+/// manifest.
+///
+/// `Vite` owns its data behind [`Arc`]s, so cloning it is cheap and clones share
+/// the same manifest and mode. It is `Send + Sync`, and dropping the last clone frees
+/// everything.
+#[derive(Debug, Clone)]
 pub struct Vite {
-    pub(crate) manifest: Option<Manifest>,
+    pub(crate) live: Arc<LiveState>,
+    pub(crate) manifest_path: Option<Arc<str>>,
+    pub(crate) hot_file_path: Option<Arc<str>>,
+    pub(crate) heart_beat_timeout: Option<Duration>,
     pub(crate) entrypoints: Entrypoints,
-    pub(crate) mode: ViteMode,
-    pub(crate) dev_server_host: &'static str,
-    pub(crate) prefix: Option<&'static str>,
-    pub(crate) app_url: &'static str,
+    pub(crate) dev_server_tls: Arc<DevServerTls>,
+    pub(crate) proxy_dev_server: bool,
+    pub(crate) backend_hmr_events: bool,
+    pub(crate) prefix: Option<Arc<str>>,
+    pub(crate) app_url: Arc<str>,
+    pub(crate) tags_options: TagsOptions,
 }
 
 impl Vite {
     /// Creates a new Vite instance.
     ///
     /// # Arguments
-    /// * `config`  - a [`ViteConfig<'_>`] instance.
+    /// * `config`  - a [`ViteConfig`] instance.
     ///
     /// # Errors
-    /// Returns `Err` if the given manifest path is not valid.
+    /// Returns a `ViteError` whose [`ViteErrorKind`] tells what went wrong:
+    /// - `MissingFile`, `Io` or `JsonParse` if the manifest can't be loaded;
+    /// - `UnknownEntrypoint` if, in Manifest mode, an entrypoint isn't in the manifest;
+    /// - `DevServerUnreachable` if the dev-server is down and there is no manifest;
+    /// - `InvalidConfig` if [`ViteConfig::build`] finds problems in the config.
     ///
     /// # Example
     /// ```rust
@@ -57,80 +85,279 @@ impl Vite {
     /// ```
     ///
     /// [`ViteConfig`]: crate::config::ViteConfig
-    pub async fn new(config: ViteConfig<'_>) -> Result<Vite, ViteError> {
-        let dev_host = Box::leak(
-            config
-                .server_host
-                .unwrap_or("http://localhost:5173")
-                .to_string()
-                .into_boxed_str(),
-        );
+    /// [`ViteErrorKind`]: crate::error::ViteErrorKind
+    #[cfg(feature = "async")]
+    pub async fn new(config: ViteConfig) -> Result<Vite, ViteError> {
+        let setup = Setup::new(config)?;
 
-        let mode = match config.force_mode {
+        let mode = match setup.mode {
             Some(mode) => mode,
             None => {
                 ViteMode::discover(
-                    config.use_heart_beat_check,
-                    config.enable_dev_server,
-                    dev_host,
-                    config.heart_beat_retries_limit.unwrap(),
+                    setup.config.use_heart_beat_check,
+                    setup.config.enable_dev_server,
+                    setup.is_production,
+                    &setup.dev_host,
+                    &setup.config.heart_beat_options(Arc::clone(&setup.tls)),
                 )
-                .await
+                .await?
             }
         };
 
+        Vite::from_setup(setup, mode)
+    }
+
+    /// Does the same as [`Vite::new`] without an async runtime: the dev-server is
+    /// probed through a plain blocking HTTP request, which makes it usable from
+    /// CLI tools, build scripts and synchronous frameworks.
+    ///
+    /// # Errors
+    /// Returns the same errors as [`Vite::new`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use vite_rust::{Vite, ViteConfig, ViteMode};
+    ///
+    /// let vite = Vite::new_blocking(
+    ///     ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+    ///         .set_force_mode(ViteMode::Manifest),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(ViteMode::Manifest, vite.mode());
+    /// ```
+    pub fn new_blocking(config: ViteConfig) -> Result<Vite, ViteError> {
+        let setup = Setup::new(config)?;
+
+        let mode = match setup.mode {
+            Some(mode) => mode,
+            None => ViteMode::discover_blocking(
+                setup.config.use_heart_beat_check,
+                setup.config.enable_dev_server,
+                setup.is_production,
+                &setup.dev_host,
+                &setup.config.heart_beat_options(Arc::clone(&setup.tls)),
+            ),
+        };
+
+        Vite::from_setup(setup, mode)
+    }
+
+    /// Finishes setting Vite up once its mode is known.
+    fn from_setup(setup: Setup, mode: ViteMode) -> Result<Vite, ViteError> {
+        let Setup {
+            config,
+            dev_host,
+            tls,
+            probed_dev_server,
+            ..
+        } = setup;
+
         let manifest = if mode.eq(&ViteMode::Manifest) || config.entrypoints.is_none() {
-            if let Some(manifest_path) = config.manifest_path {
-                Some(Manifest::new(manifest_path)?)
-            } else {
-                panic!(
-                    "Tried to start Vite in Manifest mode, but no manifest.json file has been set."
-                );
+            match &config.manifest_path {
+                Some(manifest_path) => Some(Arc::new(ReloadableManifest::new(manifest_path)?)),
+                None if probed_dev_server && mode.eq(&ViteMode::Manifest) => {
+                    return Err(ViteError::new(
+                        format!(
+                            "Vite dev-server at {dev_host} is unreachable, and no manifest.json file has been set to fall back to."
+                        ),
+                        ViteErrorKind::DevServerUnreachable {
+                            url: dev_host.to_string(),
+                        },
+                    ));
+                }
+                None if mode.eq(&ViteMode::Manifest) => {
+                    return Err(ViteError::new(
+                        "Tried to start Vite in Manifest mode, but no manifest.json file has been set.",
+                        ViteErrorKind::InvalidConfig,
+                    ));
+                }
+                None => None,
             }
         } else {
             None
         };
 
-        let entrypoints: Entrypoints = match config.entrypoints {
-            Some(entrypoints) => entrypoints.into_iter().map(|entry| entry.into()).collect(),
-            None => match &manifest {
-                Some(manifest) => manifest
-                    .get_manifest_entries()
-                    .into_iter()
-                    .map(|entry| entry.into())
-                    .collect(),
-                None => {
-                    panic!("Tried to start Vite without entrypoints set nor manifest.json file");
+        let entrypoints: Entrypoints = match (config.entrypoints, &manifest) {
+            (Some(entrypoints), manifest) => {
+                if let (ViteMode::Manifest, Some(manifest)) = (&mode, manifest) {
+                    let manifest = manifest.current();
+                    if let Some(entry) = entrypoints.iter().find(|entry| !manifest.has_chunk(entry))
+                    {
+                        return Err(ViteError::new(
+                            format!(r#"Entrypoint "{entry}" is not present in the manifest."#),
+                            ViteErrorKind::UnknownEntrypoint {
+                                entrypoint: entry.to_string(),
+                            },
+                        ));
+                    }
                 }
-            },
+
+                entrypoints.into_iter().map(|entry| entry.into()).collect()
+            }
+            (None, Some(manifest)) => manifest
+                .current()
+                .get_manifest_entries()
+                .into_iter()
+                .map(|entry| entry.into())
+                .collect(),
+            (None, None) => {
+                return Err(ViteError::new(
+                    "Tried to start Vite without entrypoints set nor manifest.json file.",
+                    ViteErrorKind::InvalidConfig,
+                ));
+            }
+        };
+
+        let prefix = resolve_prefix(config.prefix.as_deref());
+
+        let app_url = resolve_app_url(config.app_url.as_deref());
+
+        let dev_assets_url = match config.proxy_dev_server {
+            true => Arc::clone(&app_url),
+            false => Arc::clone(&dev_host),
         };
 
-        let prefix = resolve_prefix(config.prefix);
+        let live = LiveState {
+            current: RwLock::new(LiveMode {
+                mode,
+                dev_server_host: dev_host,
+                dev_assets_url,
+            }),
+            manifest: OnceLock::new(),
+            subscribers: Mutex::new(Vec::new()),
+        };
 
-        let app_url = resolve_app_url(config.app_url);
+        if let Some(manifest) = manifest {
+            let _ = live.manifest.set(manifest);
+        }
 
         Ok(Vite {
+            live: Arc::new(live),
+            manifest_path: config.manifest_path.map(Into::into),
+            hot_file_path: config.hot_file_path.map(Into::into),
+            heart_beat_timeout: config.heart_beat_timeout,
             entrypoints,
-            manifest,
-            mode,
-            dev_server_host: dev_host,
+            dev_server_tls: tls,
+            proxy_dev_server: config.proxy_dev_server,
+            backend_hmr_events: config.backend_hmr_events,
             prefix,
             app_url,
+            tags_options: TagsOptions {
+                subresource_integrity: config.subresource_integrity,
+                prefetch_depth: config.prefetch_depth,
+                style_sheet_extensions: config.style_sheet_extensions.into(),
+            },
         })
     }
 
-    /// Generates assets HTML tags from `manifest.json` file.
+    /// Generates assets HTML tags from `manifest.json` file for the configured
+    /// entrypoints.
     ///
     /// # Errors
-    /// Returns a `ViteError` if there is no Manifest.
-    ///
-    /// # Panics
-    /// Might panic if the target file doesn't exist.
+    /// Returns a `ViteError` if there is no Manifest, or if a chunk
+    /// imports another one that is missing from it.
     pub fn get_tags(&self) -> Result<String, ViteError> {
-        match &self.manifest {
-            Some(manifest) => {
-                Ok(manifest.generate_html_tags(&self.entrypoints, self.prefix, self.app_url))
-            }
+        self.get_tags_for(self.entrypoints(), None)
+    }
+
+    /// Generates assets HTML tags from `manifest.json` file for the given
+    /// entrypoints only, regardless of the ones set in [`ViteConfig`].
+    ///
+    /// Useful when a single `Vite` instance serves many pages that need
+    /// different bundles.
+    ///
+    /// # Arguments
+    /// * `entrypoints` - the root-relative paths of the entries to resolve,
+    ///   e.g. `&["src/pages/admin.tsx"]`;
+    /// * `nonce`       - an optional CSP nonce to add to every generated tag.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if there is no Manifest, or if a chunk
+    /// imports another one that is missing from it.
+    ///
+    /// # Example
+    /// ```rust
+    /// use vite_rust::{Vite, ViteConfig};
+    ///
+    /// fn main() {
+    ///     let vite_config = ViteConfig::default()
+    ///         .set_manifest_path("tests/test-manifest.json")
+    ///         .set_entrypoints(vec!["views/foo.js", "views/bar.js"])
+    ///         .set_force_mode(vite_rust::ViteMode::Manifest);
+    ///
+    ///     let vite = Vite::new_blocking(vite_config).unwrap();
+    ///
+    ///     let expected =
+    ///         r#"<link rel="stylesheet" href="/assets/shared-ChJ_j-JJ.css" />
+    ///         <script type="module" src="/assets/bar-gkvgaI9m.js"></script>
+    ///         <link rel="modulepreload" href="/assets/shared-B7PI925R.js" />"#;
+    ///
+    ///     let expected = expected.replace("\t", "     ")
+    ///         .lines()
+    ///         .map(str::trim)
+    ///         .collect::<Vec::<&str>>()
+    ///         .join("\n");
+    ///
+    ///     assert_eq!(vite.get_tags_for(&["views/bar.js"], None).unwrap(), expected);
+    /// }
+    /// ```
+    ///
+    /// [`ViteConfig`]: crate::config::ViteConfig
+    pub fn get_tags_for<E: AsRef<str>>(
+        &self,
+        entrypoints: &[E],
+        nonce: Option<&str>,
+    ) -> Result<String, ViteError> {
+        self.get_manifest_tags_for(entrypoints, nonce)
+            .map(|tags| tags_to_html(&tags))
+    }
+
+    /// Does the same as [`Vite::get_tags_for`], but returns structured [`Tag`]s
+    /// instead of HTML, so that they can be changed or rendered in other ways.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if there is no Manifest, or if a chunk
+    /// imports another one that is missing from it.
+    ///
+    /// # Example
+    /// ```rust
+    /// use vite_rust::{Vite, ViteConfig, TagKind};
+    ///
+    /// fn main() {
+    ///     let vite_config = ViteConfig::default()
+    ///         .set_manifest_path("tests/test-manifest.json")
+    ///         .set_entrypoints(vec!["views/bar.js"])
+    ///         .set_force_mode(vite_rust::ViteMode::Manifest);
+    ///
+    ///     let vite = Vite::new_blocking(vite_config).unwrap();
+    ///
+    ///     let mut tags = vite.get_manifest_tags_for(&["views/bar.js"], None).unwrap();
+    ///     for tag in tags.iter_mut() {
+    ///         if tag.kind() == TagKind::ModuleScript {
+    ///             tag.set_attribute("fetchpriority", "high");
+    ///         }
+    ///     }
+    ///
+    ///     assert_eq!(
+    ///         tags[1].to_html(),
+    ///         r#"<script type="module" src="/assets/bar-gkvgaI9m.js" fetchpriority="high"></script>"#
+    ///     );
+    /// }
+    /// ```
+    pub fn get_manifest_tags_for<E: AsRef<str>>(
+        &self,
+        entrypoints: &[E],
+        nonce: Option<&str>,
+    ) -> Result<Vec<Tag>, ViteError> {
+        match self.manifest() {
+            Some(manifest) => manifest.current().generate_tags(
+                entrypoints,
+                self.prefix.as_deref(),
+                &self.app_url,
+                &self.tags_options,
+                nonce,
+            ),
             None => Err(ViteError::new(
                 "Tried to get html tags from manifest, but there is no manifest file.",
                 ViteErrorKind::Manifest,
@@ -138,20 +365,112 @@ impl Vite {
         }
     }
 
+    /// Generates [RFC 8288] `Link` header values for the assets of the configured
+    /// entrypoints, as resolved from the `manifest.json` file. They're the same assets
+    /// [`Vite::get_tags`] generates tags for, but can be sent before the template is
+    /// rendered, either as `Link` headers or in a 103 Early Hints response.
+    ///
+    /// Multiple values can be joined with `", "` into a single `Link` header.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if there is no Manifest, or if a chunk
+    /// imports another one that is missing from it.
+    ///
+    /// # Example
+    /// ```rust
+    /// use vite_rust::{Vite, ViteConfig};
+    ///
+    /// fn main() {
+    ///     let vite_config = ViteConfig::default()
+    ///         .set_manifest_path("tests/test-manifest.json")
+    ///         .set_entrypoints(vec!["views/bar.js"])
+    ///         .set_force_mode(vite_rust::ViteMode::Manifest);
+    ///
+    ///     let vite = Vite::new_blocking(vite_config).unwrap();
+    ///
+    ///     assert_eq!(
+    ///         vite.get_link_headers().unwrap().join(", "),
+    ///         "</assets/shared-ChJ_j-JJ.css>; rel=preload; as=style, \
+    ///         </assets/bar-gkvgaI9m.js>; rel=modulepreload, \
+    ///         </assets/shared-B7PI925R.js>; rel=modulepreload"
+    ///     );
+    /// }
+    /// ```
+    ///
+    /// [RFC 8288]: https://www.rfc-editor.org/rfc/rfc8288
+    pub fn get_link_headers(&self) -> Result<Vec<String>, ViteError> {
+        self.get_link_headers_for(self.entrypoints())
+    }
+
+    /// Does the same as [`Vite::get_link_headers`], but for the given entrypoints
+    /// instead of the configured ones.
+    ///
+    /// # Arguments
+    /// * `entrypoints` - the root-relative paths of the entries to resolve.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if there is no Manifest, or if a chunk
+    /// imports another one that is missing from it.
+    pub fn get_link_headers_for<E: AsRef<str>>(
+        &self,
+        entrypoints: &[E],
+    ) -> Result<Vec<String>, ViteError> {
+        match self.manifest() {
+            Some(manifest) => manifest.current().generate_link_headers(
+                entrypoints,
+                self.prefix.as_deref(),
+                &self.app_url,
+                &self.tags_options,
+            ),
+            None => Err(ViteError::new(
+                "Tried to get link headers from manifest, but there is no manifest file.",
+                ViteErrorKind::Manifest,
+            )),
+        }
+    }
+
     /// Generates scripts and stylesheet link HTML tags referencing
     /// the entrypoints directly from the Vite dev-server.
     pub fn get_development_scripts(&self) -> Result<String, ViteError> {
+        self.get_development_scripts_for(self.entrypoints(), None)
+    }
+
+    /// Generates scripts and stylesheet link HTML tags referencing the
+    /// given entrypoints directly from the Vite dev-server.
+    ///
+    /// # Arguments
+    /// * `entrypoints` - the root-relative paths of the entries to reference;
+    /// * `nonce`       - an optional CSP nonce to add to every generated tag.
+    pub fn get_development_scripts_for<E: AsRef<str>>(
+        &self,
+        entrypoints: &[E],
+        nonce: Option<&str>,
+    ) -> Result<String, ViteError> {
+        self.get_development_tags_for(entrypoints, nonce)
+            .map(|tags| tags_to_html(&tags))
+    }
+
+    /// Does the same as [`Vite::get_development_scripts_for`], but returns structured
+    /// [`Tag`]s instead of HTML.
+    pub fn get_development_tags_for<E: AsRef<str>>(
+        &self,
+        entrypoints: &[E],
+        nonce: Option<&str>,
+    ) -> Result<Vec<Tag>, ViteError> {
         let mut tags = vec![];
 
-        for entry in self.entrypoints.iter() {
-            if entry.ends_with(".css") {
-                tags.push(Asset::StyleSheet(self.get_asset_url(entry)?).into_html());
+        for entry in entrypoints.iter() {
+            let entry = entry.as_ref();
+            let asset = if is_style_sheet(entry, &self.tags_options.style_sheet_extensions) {
+                Asset::StyleSheet(self.get_asset_url(entry)?)
             } else {
-                tags.push(Asset::EntryPoint(self.get_asset_url(entry)?).into_html());
-            }
+                Asset::EntryPoint(self.get_asset_url(entry)?)
+            };
+
+            tags.extend(asset.into_tag(None, nonce));
         }
 
-        Ok(tags.join("\n"))
+        Ok(tags)
     }
 
     /// Generates HTML tags considering the current [`ViteMode`]:
@@ -160,15 +479,50 @@ impl Vite {
     /// -   If `Manifest` mode, calls `Vite::get_tags()` and return the assets HTML tags.
     ///
     /// # Errors
-    /// Returns a `ViteError` instance if mode is `Manifest` and there is no Manifest.
+    /// Returns a `ViteError` instance if mode is `Manifest` and there is no Manifest, or if a chunk
+    /// imports another one that is missing from it.
     pub fn get_resolved_vite_scripts(&self) -> Result<String, ViteError> {
-        match self.mode {
-            ViteMode::Development => Ok(format!(
-                "{}\n{}",
-                self.get_development_scripts()?,
-                self.get_hmr_script()
-            )),
-            ViteMode::Manifest => self.get_tags(),
+        self.get_resolved_vite_scripts_for(self.entrypoints(), None)
+    }
+
+    /// Does the same as [`Vite::get_resolved_vite_scripts`], but for the given
+    /// entrypoints instead of the configured ones.
+    ///
+    /// # Arguments
+    /// * `entrypoints` - the root-relative paths of the entries to resolve;
+    /// * `nonce`       - an optional CSP nonce to add to every generated tag.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` instance if mode is `Manifest` and there is no Manifest, or if a chunk
+    /// imports another one that is missing from it.
+    pub fn get_resolved_vite_scripts_for<E: AsRef<str>>(
+        &self,
+        entrypoints: &[E],
+        nonce: Option<&str>,
+    ) -> Result<String, ViteError> {
+        self.get_resolved_tags_for(entrypoints, nonce)
+            .map(|tags| tags_to_html(&tags))
+    }
+
+    /// Does the same as [`Vite::get_resolved_vite_scripts_for`], but returns structured
+    /// [`Tag`]s instead of HTML.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` instance if mode is `Manifest` and there is no Manifest, or if a chunk
+    /// imports another one that is missing from it.
+    pub fn get_resolved_tags_for<E: AsRef<str>>(
+        &self,
+        entrypoints: &[E],
+        nonce: Option<&str>,
+    ) -> Result<Vec<Tag>, ViteError> {
+        match self.mode() {
+            ViteMode::Development => {
+                let mut tags = self.get_development_tags_for(entrypoints, nonce)?;
+                tags.extend(self.get_hmr_tag(nonce));
+                tags.extend(self.get_backend_events_tag(nonce));
+                Ok(tags)
+            }
+            ViteMode::Manifest => self.get_manifest_tags_for(entrypoints, nonce),
         }
     }
 
@@ -176,17 +530,75 @@ impl Vite {
     ///
     /// If [`ViteMode`] is set to `Manifest`, only an empty string is returned.
     pub fn get_hmr_script(&self) -> String {
-        match self.mode {
+        self.get_hmr_script_with_nonce(None)
+    }
+
+    /// Does the same as [`Vite::get_hmr_script`], adding the given CSP nonce
+    /// to the script tag.
+    pub fn get_hmr_script_with_nonce(&self, nonce: Option<&str>) -> String {
+        let tags = self
+            .get_hmr_tag(nonce)
+            .into_iter()
+            .chain(self.get_backend_events_tag(nonce))
+            .collect::<Vec<_>>();
+
+        tags_to_html(&tags)
+    }
+
+    /// Returns the Hot Module Reload client script as a structured [`Tag`].
+    ///
+    /// If [`ViteMode`] is set to `Manifest`, `None` is returned.
+    pub fn get_hmr_tag(&self, nonce: Option<&str>) -> Option<Tag> {
+        match self.mode() {
             ViteMode::Development => {
-                format!(
-                    r#"<script type="module" src="{}/{}"></script>"#,
-                    &self.dev_server_host, CLIENT_SCRIPT_PATH
-                )
+                let tag = Tag::new(TagKind::ModuleScript)
+                    .with_attribute("type", "module")
+                    .with_attribute(
+                        "src",
+                        format!("{}/{}", self.dev_assets_url(), CLIENT_SCRIPT_PATH),
+                    );
+
+                Some(with_security_attributes(tag, None, nonce))
             }
-            ViteMode::Manifest => "".to_string(),
+            ViteMode::Manifest => None,
         }
     }
 
+    /// Returns the inline script showing the reloads and errors sent by the backend
+    /// through Vite's HMR channel, if [`ViteConfig::backend_hmr_events`] is enabled.
+    /// They are relayed to the browser by `vite-rs-plugin`.
+    ///
+    /// If [`ViteMode`] is set to `Manifest`, `None` is returned.
+    pub fn get_backend_events_tag(&self, nonce: Option<&str>) -> Option<Tag> {
+        if !self.backend_hmr_events || self.mode() == ViteMode::Manifest {
+            return None;
+        }
+
+        let script = format!(
+            r#"
+                import {{ createHotContext }} from '{}/{}'
+                const hot = createHotContext('/@vite-rust/backend')
+                hot.on('{}', () => location.reload())
+                hot.on('{}', (err) => {{
+                    const ErrorOverlay = customElements.get('vite-error-overlay')
+                    if (!ErrorOverlay) return console.error('[vite-rust]', err.message)
+                    document.querySelectorAll('vite-error-overlay').forEach((overlay) => overlay.close())
+                    document.body.appendChild(new ErrorOverlay(err))
+                }})
+            "#,
+            self.dev_assets_url(),
+            CLIENT_SCRIPT_PATH,
+            BACKEND_RELOAD_EVENT,
+            BACKEND_ERROR_EVENT
+        );
+
+        let tag = Tag::new(TagKind::InlineScript)
+            .with_attribute("type", "module")
+            .with_content(script);
+
+        Some(with_security_attributes(tag, None, nonce))
+    }
+
     /// Returns the bundled file by the given original file's path. If it is not present in the
     /// manifest file, an empty string is returned.
     ///
@@ -195,10 +607,14 @@ impl Vite {
     pub fn get_asset_url(&self, path: &str) -> Result<String, ViteError> {
         let path = path.strip_prefix("/").unwrap_or(path).replace("'", "");
 
-        match &self.mode {
-            ViteMode::Development => Ok(format!("{}/{}", self.dev_server_host, path)),
-            ViteMode::Manifest => match &self.manifest {
-                Some(manifest) => Ok(manifest.get_asset_url(&path, self.prefix, self.app_url)),
+        match self.mode() {
+            ViteMode::Development => Ok(format!("{}/{}", self.dev_assets_url(), path)),
+            ViteMode::Manifest => match self.manifest() {
+                Some(manifest) => Ok(manifest.current().get_asset_url(
+                    &path,
+                    self.prefix.as_deref(),
+                    &self.app_url,
+                )),
                 None => Err(ViteError::new(
                     "Tried to get asset's URL from manifest, but there is no manifest file.",
                     ViteErrorKind::Manifest,
@@ -211,16 +627,35 @@ impl Vite {
     ///
     /// [react fast refresh script]: https://vite.dev/guide/backend-integration
     pub fn get_react_script(&self) -> String {
-        format!(
-            r#"<script type="module">
+        self.get_react_script_with_nonce(None)
+    }
+
+    /// Does the same as [`Vite::get_react_script`], adding the given CSP nonce
+    /// to the inline script tag.
+    pub fn get_react_script_with_nonce(&self, nonce: Option<&str>) -> String {
+        self.get_react_tag(nonce).to_html()
+    }
+
+    /// Returns the [react fast refresh script] as a structured, inline [`Tag`].
+    ///
+    /// [react fast refresh script]: https://vite.dev/guide/backend-integration
+    pub fn get_react_tag(&self, nonce: Option<&str>) -> Tag {
+        let preamble = format!(
+            r#"
                 import RefreshRuntime from '{}/@react-refresh'
                 RefreshRuntime.injectIntoGlobalHook(window)
                 window.$RefreshReg$ = () => {{}}
                 window.$RefreshSig$ = () => (type) => type
                 window.__vite_plugin_react_preamble_installed__ = true
-            </script>"#,
-            &self.dev_server_host
-        )
+            "#,
+            self.dev_assets_url()
+        );
+
+        let tag = Tag::new(TagKind::InlineScript)
+            .with_attribute("type", "module")
+            .with_content(preamble);
+
+        with_security_attributes(tag, None, nonce)
     }
 
     /// Returns the current `manifest.json` file hash. Might be used for
@@ -228,25 +663,424 @@ impl Vite {
     ///
     /// The resultant string is a hex-encoded MD5 hash.
     #[inline]
-    pub fn get_hash(&self) -> Option<&str> {
-        match &self.manifest {
-            Some(manifest) => Some(manifest.get_hash()),
-            None => None,
+    pub fn get_hash(&self) -> Option<String> {
+        self.manifest()
+            .map(|manifest| manifest.current().get_hash().to_string())
+    }
+
+    /// Reads and parses the `manifest.json` file again, replacing the manifest
+    /// in use. Useful when new assets are built or deployed while the server is
+    /// running.
+    ///
+    /// The new manifest is parsed before being swapped in at once, so concurrent
+    /// calls to the other methods either see the old or the new manifest.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if there is no Manifest, or if the file can't be read
+    /// or parsed. The current manifest is kept in this case.
+    pub fn reload_manifest(&self) -> Result<(), ViteError> {
+        match self.manifest() {
+            Some(manifest) => manifest.reload().map(|_| ()),
+            None => Err(ViteError::new(
+                "Tried to reload the manifest, but there is no manifest file.",
+                ViteErrorKind::Manifest,
+            )),
+        }
+    }
+
+    /// Spawns a background thread that checks the `manifest.json` file every
+    /// `poll_interval` and reloads it whenever its content changes, e.g. when
+    /// running `vite build --watch` alongside the server.
+    ///
+    /// Watching stops once the returned [`ManifestWatcher`] is dropped, so keep it
+    /// alive for as long as the manifest should be watched.
+    ///
+    /// # Errors
+    /// Returns a `ViteError` if there is no Manifest.
+    pub fn watch_manifest(&self, poll_interval: Duration) -> Result<ManifestWatcher, ViteError> {
+        match self.manifest() {
+            Some(manifest) => Ok(ManifestWatcher::spawn(Arc::clone(manifest), poll_interval)),
+            None => Err(ViteError::new(
+                "Tried to watch the manifest, but there is no manifest file.",
+                ViteErrorKind::Manifest,
+            )),
         }
     }
 
+    /// Returns the configured entrypoints, which are the default set used by the
+    /// methods that don't take entrypoints as argument.
+    pub fn entrypoints(&self) -> &[Arc<str>] {
+        &self.entrypoints
+    }
+
     /// Returns the Vite instance's dev-server URL.
-    pub fn get_dev_server_url(&self) -> &str {
-        self.dev_server_host
+    ///
+    /// It is the dev-server's real URL even if it is proxied by the application
+    /// (see [`ViteConfig::proxy_dev_server`]).
+    ///
+    /// It might change at runtime if the dev-server is found through a hot file
+    /// and restarts on another port (see [`Vite::monitor_dev_server`]).
+    pub fn get_dev_server_url(&self) -> String {
+        self.dev_server_host().to_string()
+    }
+
+    /// Returns a [`DevServerProxy`] forwarding the dev-server requests made to the
+    /// application, such as `/@vite/client` or the entrypoints' sources, to Vite.
+    ///
+    /// Enable [`ViteConfig::proxy_dev_server`] so that the generated URLs point at
+    /// the application itself.
+    ///
+    /// # Errors
+    /// Returns an `InvalidConfig` error if no HTTP client can be set up with the
+    /// configured dev-server certificates.
+    #[cfg(feature = "proxy")]
+    pub fn dev_server_proxy(&self) -> Result<DevServerProxy, ViteError> {
+        DevServerProxy::new(self.clone())
+    }
+
+    /// Returns the current mode, which might change at runtime while the dev-server
+    /// is monitored (see [`Vite::monitor_dev_server`]).
+    pub fn mode(&self) -> ViteMode {
+        self.live_mode().mode
+    }
+
+    /// Spawns a background thread that checks whether the dev-server is running every
+    /// `poll_interval`, switching this instance and its clones to `Development` mode
+    /// when it comes up, and to `Manifest` mode when it goes away. The manifest is
+    /// (re)loaded on each switch to `Manifest` mode.
+    ///
+    /// If a hot file is configured, it is read instead of pinging the dev-server, and
+    /// the dev-server URL it holds replaces the current one. A forced mode is
+    /// overridden as well.
+    ///
+    /// Monitoring stops once the returned [`DevServerMonitor`] is dropped, so keep it
+    /// alive for as long as the dev-server should be monitored.
+    pub fn monitor_dev_server(&self, poll_interval: Duration) -> DevServerMonitor {
+        DevServerMonitor::spawn(self.clone(), poll_interval)
+    }
+
+    /// Spawns a background thread listening to the dev-server's HMR WebSocket, e.g. to
+    /// invalidate caches of rendered templates whenever the frontend changes, or to log
+    /// its build errors. The thread retries connecting every `retry_interval` while
+    /// the dev-server is down.
+    ///
+    /// Listening stops once the returned [`HmrListener`] is dropped.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use vite_rust::{HmrEvent, Vite, ViteConfig};
+    ///
+    /// let vite = Vite::new_blocking(ViteConfig::default().set_entrypoints(["src/main.ts"])).unwrap();
+    /// let hmr = vite.listen_hmr(Duration::from_secs(2));
+    ///
+    /// for event in hmr.iter() {
+    ///     match event {
+    ///         HmrEvent::Update { .. } | HmrEvent::FullReload { .. } => { /* clear caches */ }
+    ///         HmrEvent::Error { err } => log::error!("Vite failed to build: {}", err.message),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "hmr")]
+    pub fn listen_hmr(&self, retry_interval: Duration) -> HmrListener {
+        HmrListener::spawn(self.clone(), retry_interval)
+    }
+
+    /// Makes the browser reload the page through Vite's HMR channel, e.g. when a
+    /// server-side template changes. Nothing is sent in `Manifest` mode.
+    ///
+    /// Requires `vite-rs-plugin` to relay the event, and
+    /// [`ViteConfig::backend_hmr_events`] to handle it in the browser.
+    ///
+    /// # Errors
+    /// Returns a `DevServerUnreachable` error if the dev-server's HMR WebSocket
+    /// can't be reached.
+    #[cfg(feature = "hmr")]
+    pub fn trigger_full_reload(&self) -> Result<(), ViteError> {
+        self.send_hmr_event(BACKEND_RELOAD_EVENT, &serde_json::Value::Null)
+    }
+
+    /// Shows the error in Vite's error overlay, e.g. when a template or handler
+    /// fails while developing. Nothing is sent in `Manifest` mode.
+    ///
+    /// Requires `vite-rs-plugin` to relay the event, and
+    /// [`ViteConfig::backend_hmr_events`] to handle it in the browser.
+    ///
+    /// # Errors
+    /// Returns a `DevServerUnreachable` error if the dev-server's HMR WebSocket
+    /// can't be reached.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # let vite = vite_rust::Vite::new_blocking(vite_rust::ViteConfig::default().set_entrypoints(["src/main.ts"])).unwrap();
+    /// # let err = std::io::Error::other("template `users/show.html` not found");
+    /// use vite_rust::HmrError;
+    ///
+    /// vite.show_error_overlay(&HmrError::from_error(&err)).unwrap();
+    /// ```
+    #[cfg(feature = "hmr")]
+    pub fn show_error_overlay(&self, error: &HmrError) -> Result<(), ViteError> {
+        self.send_hmr_event(BACKEND_ERROR_EVENT, error)
+    }
+
+    /// Sends a custom event to the dev-server through its HMR WebSocket, which Vite
+    /// plugins receive with `server.ws.on(event, listener)`. Nothing is sent in
+    /// `Manifest` mode.
+    ///
+    /// # Errors
+    /// Returns a `DevServerUnreachable` error if the dev-server's HMR WebSocket
+    /// can't be reached, and an `InvalidConfig` error if `data` can't be serialized.
+    #[cfg(feature = "hmr")]
+    pub fn send_hmr_event<T: Serialize + ?Sized>(
+        &self,
+        event: &str,
+        data: &T,
+    ) -> Result<(), ViteError> {
+        if self.mode() == ViteMode::Manifest {
+            return Ok(());
+        }
+
+        let data = serde_json::to_value(data).map_err(|err| {
+            ViteError::with_source(
+                format!("Failed to serialize the data of the \"{event}\" HMR event."),
+                ViteErrorKind::InvalidConfig,
+                err,
+            )
+        })?;
+
+        let dev_server_host = self.dev_server_host();
+        send_custom_event(
+            &dev_server_host,
+            self.heart_beat_timeout,
+            &self.dev_server_tls,
+            event,
+            data,
+        )
+        .map_err(|err| {
+            ViteError::with_source(
+                format!(
+                    "Failed to send the \"{event}\" event to the Vite HMR WebSocket at {dev_server_host}: {err}"
+                ),
+                ViteErrorKind::DevServerUnreachable {
+                    url: dev_server_host.to_string(),
+                },
+                err,
+            )
+        })
+    }
+
+    /// Returns a receiver of every mode change happening from now on, e.g. to log
+    /// them or to clear caches of rendered templates.
+    ///
+    /// # Example
+    /// ```rust
+    /// use vite_rust::{Vite, ViteConfig, ViteMode};
+    ///
+    /// let vite = Vite::new_blocking(
+    ///     ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+    ///         .set_force_mode(ViteMode::Manifest),
+    /// )
+    /// .unwrap();
+    ///
+    /// let changes = vite.subscribe_mode();
+    /// let _monitor = vite.monitor_dev_server(std::time::Duration::from_secs(2));
+    ///
+    /// std::thread::spawn(move || {
+    ///     for mode in changes {
+    ///         println!("Vite switched to {mode:?} mode.");
+    ///     }
+    /// });
+    /// ```
+    pub fn subscribe_mode(&self) -> Receiver<ViteMode> {
+        let (sender, receiver) = mpsc::channel();
+        self.live
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sender);
+
+        receiver
+    }
+
+    pub(crate) fn manifest(&self) -> Option<&Arc<ReloadableManifest>> {
+        self.live.manifest.get()
     }
 
-    /// Returns the current Vite instance's mode.
-    pub fn mode(&self) -> &ViteMode {
-        &self.mode
+    fn live_mode(&self) -> RwLockReadGuard<'_, LiveMode> {
+        self.live
+            .current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
     }
+
+    pub(crate) fn dev_server_host(&self) -> Arc<str> {
+        Arc::clone(&self.live_mode().dev_server_host)
+    }
+
+    fn dev_assets_url(&self) -> Arc<str> {
+        Arc::clone(&self.live_mode().dev_assets_url)
+    }
+
+    /// Points this instance and its clones at the dev-server running at `host`,
+    /// e.g. after it restarted on another port.
+    pub(crate) fn set_dev_server_host(&self, host: &str) {
+        let mut current = self
+            .live
+            .current
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if &*current.dev_server_host == host {
+            return;
+        }
+
+        let host: Arc<str> = host.into();
+        if !self.proxy_dev_server {
+            current.dev_assets_url = Arc::clone(&host);
+        }
+        current.dev_server_host = host;
+        log::info!("Vite dev-server moved to {}.", current.dev_server_host);
+    }
+
+    /// Switches to the given mode if it isn't the current one, loading or reloading
+    /// the manifest on the way to `Manifest` mode, and notifies the subscribers.
+    pub(crate) fn switch_mode(&self, mode: ViteMode) {
+        if self.mode() == mode {
+            return;
+        }
+
+        if mode == ViteMode::Manifest {
+            self.refresh_manifest();
+        }
+
+        self.live
+            .current
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .mode = mode;
+        log::info!("Switched Vite to {mode:?} mode.");
+
+        self.live
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|subscriber| subscriber.send(mode).is_ok());
+    }
+
+    fn refresh_manifest(&self) {
+        let result = match (self.manifest(), &self.manifest_path) {
+            (Some(manifest), _) => manifest.reload().map(|_| ()),
+            (None, Some(manifest_path)) => ReloadableManifest::new(manifest_path).map(|manifest| {
+                let _ = self.live.manifest.set(Arc::new(manifest));
+            }),
+            (None, None) => Err(ViteError::new(
+                "There is no manifest.json file to switch to.",
+                ViteErrorKind::Manifest,
+            )),
+        };
+
+        if let Err(err) = result {
+            log::warn!("Failed to load the manifest while switching to Manifest mode: {err}");
+        }
+    }
+}
+
+/// The state shared by a [`Vite`] instance and its clones that might change at
+/// runtime.
+#[derive(Debug)]
+pub(crate) struct LiveState {
+    current: RwLock<LiveMode>,
+    /// Set on start, or when first switching to `Manifest` mode.
+    manifest: OnceLock<Arc<ReloadableManifest>>,
+    subscribers: Mutex<Vec<Sender<ViteMode>>>,
+}
+
+/// The current mode, and the dev-server it refers to, which might move to another
+/// URL if it's found through a hot file.
+#[derive(Debug)]
+struct LiveMode {
+    mode: ViteMode,
+    dev_server_host: Arc<str>,
+    /// Where the dev-server assets are referenced from: `dev_server_host`, or
+    /// `app_url` if the dev-server is proxied by the application.
+    dev_assets_url: Arc<str>,
+}
+
+/// What is known about a [`Vite`] instance before its mode is discovered, if it has
+/// to be.
+struct Setup {
+    config: ViteConfig,
+    dev_host: Arc<str>,
+    tls: Arc<DevServerTls>,
+    /// The mode decided by the config or the hot file, if any.
+    mode: Option<ViteMode>,
+    is_production: bool,
+    /// Whether the dev-server has been (or will be) looked for.
+    probed_dev_server: bool,
 }
 
-pub(crate) fn resolve_prefix(prefix: Option<&str>) -> Option<&'static str> {
+impl Setup {
+    fn new(config: ViteConfig) -> Result<Self, ViteError> {
+        let config = config.build().map_err(|problems| {
+            let problems = problems
+                .iter()
+                .map(|problem| format!("- {problem}"))
+                .collect::<Vec<String>>()
+                .join("\n");
+
+            ViteError::new(
+                format!("Invalid Vite config:\n{problems}"),
+                ViteErrorKind::InvalidConfig,
+            )
+        })?;
+
+        let hot_file_url = match &config.hot_file_path {
+            Some(hot_file_path) => read_hot_file(hot_file_path)?,
+            None => None,
+        };
+        if let Some(url) = &hot_file_url {
+            log::info!("Found Vite dev-server URL {url} in hot file.");
+        }
+
+        let dev_host: Arc<str> = hot_file_url
+            .as_deref()
+            .or(config.server_host.as_deref())
+            .unwrap_or("http://localhost:5173")
+            .into();
+
+        let tls = Arc::new(DevServerTls::load(&config)?);
+
+        let is_production = config.force_mode.is_none()
+            && config.hot_file_path.is_none()
+            && config.use_heart_beat_check
+            && config.enable_dev_server
+            && config.is_production();
+
+        let probed_dev_server = config.force_mode.is_none()
+            && (config.hot_file_path.is_some()
+                || config.use_heart_beat_check && config.enable_dev_server && !is_production);
+
+        let mode = match &config.force_mode {
+            Some(mode) => Some(*mode),
+            None if config.hot_file_path.is_some() => match hot_file_url {
+                Some(_) => Some(ViteMode::Development),
+                None => Some(ViteMode::Manifest),
+            },
+            None => None,
+        };
+
+        Ok(Setup {
+            config,
+            dev_host,
+            tls,
+            mode,
+            is_production,
+            probed_dev_server,
+        })
+    }
+}
+
+pub(crate) fn resolve_prefix(prefix: Option<&str>) -> Option<Arc<str>> {
     if let Some(prefix) = prefix {
         if prefix.is_empty() || prefix.eq("/") {
             return None;
@@ -255,56 +1089,361 @@ pub(crate) fn resolve_prefix(prefix: Option<&str>) -> Option<&'static str> {
         let prefix = prefix.strip_prefix("/").unwrap_or(prefix);
         let prefix = prefix.strip_suffix("/").unwrap_or(prefix);
 
-        return Some(Box::leak(prefix.to_string().into_boxed_str()));
+        return Some(prefix.into());
     }
 
     None
 }
 
-pub(crate) fn resolve_app_url(app_url: Option<&str>) -> &'static str {
-    if let Some(app_url) = app_url {
-        let app_url = app_url.strip_suffix("/").unwrap_or(app_url);
+pub(crate) fn resolve_app_url(app_url: Option<&str>) -> Arc<str> {
+    resolve_app_url_with(app_url, env::var("APP_URL").ok())
+}
 
-        return Box::leak(app_url.to_string().into_boxed_str());
+/// Same as [`resolve_app_url`], with the `APP_URL` environment variable passed in.
+fn resolve_app_url_with(app_url: Option<&str>, env_app_url: Option<String>) -> Arc<str> {
+    if let Some(app_url) = app_url {
+        return app_url.strip_suffix("/").unwrap_or(app_url).into();
     }
 
-    let app_url = if let Ok(app_url) = env::var("APP_URL") {
-        app_url.strip_suffix("/").unwrap_or(&app_url).to_string()
-    } else {
-        String::new()
-    };
-
-    Box::leak(app_url.into_boxed_str())
+    match env_app_url {
+        Some(app_url) => app_url.strip_suffix("/").unwrap_or(&app_url).into(),
+        None => "".into(),
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use std::env;
+    use std::error::Error;
+    use std::fs;
+    use std::sync::Arc;
+    use std::time::Duration;
 
-    use crate::vite::{resolve_app_url, resolve_prefix};
+    use crate::error::ViteErrorKind;
+    use crate::test_utils::{NormalizeHtmlStrings, TempFile};
+    use crate::vite::{resolve_app_url, resolve_app_url_with, resolve_prefix};
+    use crate::{Tag, TagKind, Vite, ViteConfig, ViteMode};
 
     #[test]
     fn test_resolve_prefix() {
         const EXPECTED_RESULT: &str = "bundle";
 
-        assert_eq!(EXPECTED_RESULT, resolve_prefix(Some("bundle")).unwrap());
-        assert_eq!(EXPECTED_RESULT, resolve_prefix(Some("/bundle")).unwrap());
-        assert_eq!(EXPECTED_RESULT, resolve_prefix(Some("bundle/")).unwrap());
-        assert_eq!(EXPECTED_RESULT, resolve_prefix(Some("/bundle/")).unwrap());
+        assert_eq!(EXPECTED_RESULT, &*resolve_prefix(Some("bundle")).unwrap());
+        assert_eq!(EXPECTED_RESULT, &*resolve_prefix(Some("/bundle")).unwrap());
+        assert_eq!(EXPECTED_RESULT, &*resolve_prefix(Some("bundle/")).unwrap());
+        assert_eq!(EXPECTED_RESULT, &*resolve_prefix(Some("/bundle/")).unwrap());
     }
     #[test]
     fn test_resolve_app_url() {
         const EXPECTED_RESULT: &str = "http://foo.baz";
 
-        assert_eq!(EXPECTED_RESULT, resolve_app_url(Some("http://foo.baz/")));
-        assert_eq!(EXPECTED_RESULT, resolve_app_url(Some("http://foo.baz")));
+        assert_eq!(EXPECTED_RESULT, &*resolve_app_url(Some("http://foo.baz/")));
+        assert_eq!(EXPECTED_RESULT, &*resolve_app_url(Some("http://foo.baz")));
+
+        assert_eq!(&*resolve_app_url_with(None, None), "");
+        assert_eq!(
+            &*resolve_app_url_with(None, Some("http://foo.baz/".into())),
+            EXPECTED_RESULT
+        );
+        assert_eq!(
+            &*resolve_app_url_with(Some("http://foo.baz"), Some("http://bar.baz".into())),
+            EXPECTED_RESULT
+        );
+    }
+
+    #[test]
+    fn test_scripts_for_given_entrypoints() {
+        let config = ViteConfig::default()
+            .set_manifest_path("tests/test-manifest.json")
+            .set_entrypoints(vec!["views/foo.js", "views/bar.js"]);
+
+        let dev_vite =
+            Vite::new_blocking(config.clone().set_force_mode(ViteMode::Development)).unwrap();
+        let manifest_vite = Vite::new_blocking(config.set_force_mode(ViteMode::Manifest)).unwrap();
+
+        let dev_expected =
+            r#"<script type="module" src="http://localhost:5173/views/bar.js"></script>
+            <script type="module" src="http://localhost:5173/@vite/client"></script>"#
+                .__normalize_html_strings();
+
+        let manifest_expected = r#"<link rel="stylesheet" href="/assets/shared-ChJ_j-JJ.css" />
+            <script type="module" src="/assets/bar-gkvgaI9m.js"></script>
+            <link rel="modulepreload" href="/assets/shared-B7PI925R.js" />"#
+            .__normalize_html_strings();
+
+        assert_eq!(
+            dev_expected,
+            dev_vite
+                .get_resolved_vite_scripts_for(&["views/bar.js"], None)
+                .unwrap()
+        );
+        assert_eq!(
+            manifest_expected,
+            manifest_vite
+                .get_resolved_vite_scripts_for(&["views/bar.js"], None)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_structured_tags() {
+        let vite = Vite::new_blocking(
+            ViteConfig::default()
+                .set_manifest_path("tests/test-manifest.json")
+                .set_entrypoints(vec!["views/bar.js"])
+                .set_force_mode(ViteMode::Development),
+        )
+        .unwrap();
+
+        let mut tags = vite
+            .get_resolved_tags_for(vite.entrypoints(), Some("abc"))
+            .unwrap();
 
-        assert_eq!(resolve_app_url(None), "");
+        assert_eq!(2, tags.len());
+        assert_eq!(TagKind::ModuleScript, tags[0].kind());
+        assert_eq!(Some("http://localhost:5173/@vite/client"), tags[1].url());
 
-        env::set_var("APP_URL", "http://foo.baz");
+        tags[0].set_boolean_attribute("defer");
+        assert_eq!(
+            r#"<script type="module" src="http://localhost:5173/views/bar.js" nonce="abc" defer></script>"#,
+            tags[0].to_html()
+        );
+
+        let react = vite.get_react_tag(None);
+        assert_eq!(TagKind::InlineScript, react.kind());
+        assert_eq!(None, react.url());
+        assert_eq!(vite.get_react_script(), react.to_html());
+    }
+
+    #[test]
+    fn test_style_sheet_entries() {
+        let config = ViteConfig::new(
+            "tests/styles-manifest.json",
+            ["src/app.scss", "src/main.ts"],
+        );
+
+        let vite =
+            Vite::new_blocking(config.clone().set_force_mode(ViteMode::Development)).unwrap();
+        let kinds = vite
+            .get_development_tags_for(
+                &["src/app.scss", "src/theme.STYL?inline", "src/main.ts"],
+                None,
+            )
+            .unwrap()
+            .iter()
+            .map(Tag::kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![
+                TagKind::StyleSheet,
+                TagKind::StyleSheet,
+                TagKind::ModuleScript
+            ],
+            kinds
+        );
+
+        let vite = Vite::new_blocking(
+            config
+                .set_style_sheet_extensions(["css"])
+                .set_force_mode(ViteMode::Development),
+        )
+        .unwrap();
+        let tags = vite
+            .get_development_tags_for(&["src/app.scss"], None)
+            .unwrap();
+        assert_eq!(TagKind::ModuleScript, tags[0].kind());
+    }
+
+    #[test]
+    fn test_backend_events_tag() {
+        let config = ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+            .set_server_host("http://localhost:5173")
+            .set_backend_hmr_events(true);
+
+        let vite =
+            Vite::new_blocking(config.clone().set_force_mode(ViteMode::Development)).unwrap();
+        let tag = vite.get_backend_events_tag(Some("r4nd0m")).unwrap();
+        assert_eq!(TagKind::InlineScript, tag.kind());
+        assert_eq!(Some("r4nd0m"), tag.get_attribute("nonce"));
+
+        let script = tag.content().unwrap();
+        assert!(script.contains("from 'http://localhost:5173/@vite/client'"));
+        assert!(script.contains("hot.on('vite-rust:full-reload'"));
+        assert!(script.contains("hot.on('vite-rust:error'"));
+
+        let hmr_script = vite.get_hmr_script();
+        assert_eq!(
+            2,
+            hmr_script
+                .lines()
+                .filter(|line| line.contains("<script"))
+                .count()
+        );
+
+        let vite = Vite::new_blocking(config.clone().set_force_mode(ViteMode::Manifest)).unwrap();
+        assert!(vite.get_backend_events_tag(None).is_none());
+
+        let vite = Vite::new_blocking(
+            ViteConfig::new("tests/test-manifest.json", ["views/foo.js"])
+                .set_force_mode(ViteMode::Development),
+        )
+        .unwrap();
+        assert!(vite.get_backend_events_tag(None).is_none());
+    }
 
-        assert_eq!(resolve_app_url(None), EXPECTED_RESULT);
+    #[test]
+    fn test_new_errors() {
+        let config = ViteConfig::default().set_force_mode(ViteMode::Manifest);
+
+        let error = Vite::new_blocking(config.clone()).unwrap_err();
+        assert_eq!(&ViteErrorKind::InvalidConfig, error.kind());
+
+        let error = Vite::new_blocking(config.clone().set_manifest_path("tests/unexisting.json"))
+            .unwrap_err();
+        assert_eq!(
+            &ViteErrorKind::MissingFile {
+                path: "tests/unexisting.json".into()
+            },
+            error.kind()
+        );
+        assert!(error.source().is_some());
+
+        let error = Vite::new_blocking(
+            config
+                .clone()
+                .set_manifest_path("tests/test-manifest.json")
+                .set_entrypoints(vec!["views/foo.js", "views/qux.js"]),
+        )
+        .unwrap_err();
+        assert_eq!(
+            &ViteErrorKind::UnknownEntrypoint {
+                entrypoint: "views/qux.js".to_string()
+            },
+            error.kind()
+        );
+
+        let manifest_path = TempFile::new("invalid-manifest.json");
+        fs::write(
+            &manifest_path,
+            "{\n  \"views/foo.js\": {\n    \"file\": 1\n  }\n}",
+        )
+        .unwrap();
+
+        let error =
+            Vite::new_blocking(config.set_manifest_path(manifest_path.to_str())).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ViteErrorKind::JsonParse { line: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn test_clone_shares_data() {
+        fn assert_send_sync<T: Send + Sync + Clone>() {}
+        assert_send_sync::<Vite>();
+
+        let vite = Vite::new_blocking(
+            ViteConfig::default()
+                .set_manifest_path("tests/test-manifest.json")
+                .set_prefix("bundle")
+                .set_force_mode(ViteMode::Manifest),
+        )
+        .unwrap();
+
+        let clone = vite.clone();
+        assert!(Arc::ptr_eq(
+            vite.manifest().unwrap(),
+            clone.manifest().unwrap()
+        ));
+        assert!(Arc::ptr_eq(&vite.entrypoints, &clone.entrypoints));
+
+        drop(vite);
+        assert_eq!(
+            "/bundle/assets/bar-gkvgaI9m.js",
+            clone.get_asset_url("views/bar.js").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hot_file() {
+        let hot_file = TempFile::new("hot-file");
+        let config = ViteConfig::default()
+            .set_manifest_path("tests/test-manifest.json")
+            .set_hot_file_path(hot_file.to_str());
+
+        let vite = Vite::new_blocking(config.clone()).unwrap();
+        assert_eq!(ViteMode::Manifest, vite.mode());
+
+        fs::write(&hot_file, "http://localhost:5174").unwrap();
+
+        let vite = Vite::new_blocking(config).unwrap();
+        assert_eq!(ViteMode::Development, vite.mode());
+        assert_eq!("http://localhost:5174", vite.get_dev_server_url());
+    }
+
+    #[test]
+    fn test_reload_manifest() {
+        let manifest_path = TempFile::new("reload-manifest.json");
+        fs::copy("tests/test-manifest.json", &manifest_path).unwrap();
+
+        let vite = Vite::new_blocking(
+            ViteConfig::default()
+                .set_manifest_path(manifest_path.to_str())
+                .set_entrypoints(vec!["views/bar.js"])
+                .set_force_mode(ViteMode::Manifest),
+        )
+        .unwrap();
+
+        let hash = vite.get_hash();
+        assert_eq!(
+            "/assets/bar-gkvgaI9m.js",
+            vite.get_asset_url("views/bar.js").unwrap()
+        );
+
+        let rebuilt_manifest = fs::read_to_string("tests/test-manifest.json")
+            .unwrap()
+            .replace("bar-gkvgaI9m.js", "bar-Ck2xQ8pZ.js");
+        fs::write(&manifest_path, rebuilt_manifest).unwrap();
+
+        vite.reload_manifest().unwrap();
+
+        assert_ne!(hash, vite.get_hash());
+        assert_eq!(
+            "/assets/bar-Ck2xQ8pZ.js",
+            vite.get_asset_url("views/bar.js").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_watch_manifest() {
+        let manifest_path = TempFile::new("watch-manifest.json");
+        fs::copy("tests/test-manifest.json", &manifest_path).unwrap();
+
+        let vite = Vite::new_blocking(
+            ViteConfig::default()
+                .set_manifest_path(manifest_path.to_str())
+                .set_entrypoints(vec!["views/bar.js"])
+                .set_force_mode(ViteMode::Manifest),
+        )
+        .unwrap();
+
+        let watcher = vite.watch_manifest(Duration::from_millis(10)).unwrap();
+
+        let rebuilt_manifest = fs::read_to_string("tests/test-manifest.json")
+            .unwrap()
+            .replace("bar-gkvgaI9m.js", "bar-Ck2xQ8pZ.js");
+        fs::write(&manifest_path, rebuilt_manifest).unwrap();
+
+        let mut asset_url = String::new();
+        for _ in 0..100 {
+            asset_url = vite.get_asset_url("views/bar.js").unwrap();
+            if asset_url != "/assets/bar-gkvgaI9m.js" {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
 
-        env::remove_var("APP_URL");
+        drop(watcher);
+        assert_eq!("/assets/bar-Ck2xQ8pZ.js", asset_url);
     }
 }
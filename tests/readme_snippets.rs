@@ -1,5 +1,6 @@
 #[cfg(test)]
 mod test {
+    #[cfg(feature = "async")]
     #[tokio::test]
     async fn getting_started() {
         use vite_rust::ViteConfig;
@@ -14,8 +15,8 @@ mod test {
 
         assert_eq!(
             ViteConfig {
-                entrypoints: Some(vec!["views/bar.js", "views/foo.js"]),
-                manifest_path: Some("tests/test-manifest.json"),
+                entrypoints: Some(vec!["views/bar.js".into(), "views/foo.js".into()]),
+                manifest_path: Some("tests/test-manifest.json".into()),
                 ..Default::default()
             },
             vite_config
@@ -24,6 +25,7 @@ mod test {
         assert!(hmr_script.is_empty()); // no hmr script in ViteMode::Manifest
     }
 
+    #[cfg(all(feature = "async", feature = "basic-directives"))]
     #[tokio::test]
     async fn directives() {
         use vite_rust::{features::html_directives::ViteDefaultDirectives, Vite, ViteConfig};
@@ -1,3 +1,4 @@
+import fs from 'node:fs'
 import path from 'node:path'
 import type {
   ConfigEnv,
@@ -10,17 +11,50 @@ import {
   ViteRustPluginInput,
 } from './types'
 
+const backendEvents = ['vite-rust:full-reload', 'vite-rust:error']
+
 export function resolveRustVitePlugin(
   pluginConfig: ResolvedPluginConfig,
 ): ViteRustPlugin {
   const originPlaceholder = '__rust_vite_plugin_placeholder__'
   let resolvedConfig: ResolvedConfig
+  let devServerUrl: string = pluginConfig.devServerUrl
+  let buildBase: string
+
+  function writeDescriptor(descriptorFile: string) {
+    const root = resolvedConfig.root
+    const descriptorPath = path.resolve(root, descriptorFile)
+    const manifest = resolvedConfig.build.manifest
+    const manifestPath = path.resolve(
+      root,
+      resolvedConfig.build.outDir,
+      typeof manifest === 'string' ? manifest : '.vite/manifest.json',
+    )
+
+    const descriptor = {
+      // relative to the descriptor, so that both can be moved together
+      manifestPath: path
+        .relative(path.dirname(descriptorPath), manifestPath)
+        .replaceAll('\\', '/'),
+      base: buildBase,
+      entrypoints: pluginConfig.entrypoints,
+      devServerUrl,
+    }
+
+    fs.mkdirSync(path.dirname(descriptorPath), { recursive: true })
+    fs.writeFileSync(descriptorPath, JSON.stringify(descriptor, null, 2))
+  }
 
   return {
     name: 'vite-rust-plugin',
     enforce: 'post',
 
     config: (config, env) => {
+      buildBase = resolveBase(config.base, pluginConfig, {
+        ...env,
+        command: 'build',
+      })
+
       return {
         base: resolveBase(config.base, pluginConfig, env),
         build: {
@@ -42,11 +76,53 @@ export function resolveRustVitePlugin(
       resolvedConfig = config
     },
 
+    buildStart() {
+      if (resolvedConfig.command === 'build' && pluginConfig.descriptorFile) {
+        writeDescriptor(pluginConfig.descriptorFile)
+      }
+    },
+
+    configureServer(server) {
+      const hotFile = pluginConfig.hotFile
+
+      // relays the events sent by vite-rust to the browsers, where the snippet
+      // injected by `ViteConfig::set_backend_hmr_events` handles them
+      for (const event of backendEvents) {
+        server.ws.on(event, (data) => server.ws.send(event, data))
+      }
+
+      server.httpServer?.once('listening', () => {
+        devServerUrl = trimUrlTrailingSlash(
+          server.resolvedUrls?.local[0] ?? pluginConfig.devServerUrl,
+        )
+
+        if (pluginConfig.descriptorFile) {
+          writeDescriptor(pluginConfig.descriptorFile)
+        }
+
+        if (hotFile) {
+          fs.mkdirSync(path.dirname(hotFile), { recursive: true })
+          fs.writeFileSync(hotFile, devServerUrl)
+        }
+      })
+
+      if (!hotFile) return
+
+      const removeHotFile = () => {
+        if (fs.existsSync(hotFile)) fs.rmSync(hotFile)
+      }
+
+      server.httpServer?.once('close', removeHotFile)
+      process.once('exit', removeHotFile)
+      process.once('SIGINT', () => process.exit())
+      process.once('SIGTERM', () => process.exit())
+    },
+
     transform(code) {
       if (resolvedConfig.command === 'serve') {
         const newCode = code.replace(
           originPlaceholder,
-          pluginConfig.devServerUrl,
+          devServerUrl,
         )
 
         return {
@@ -75,6 +151,8 @@ export function resolveViteRustPluginInputWithDefaults(
     outDir: config.outDir
       ? trimPathSlashes(config.outDir)
       : 'dist',
+    hotFile: config.hotFile ?? false,
+    descriptorFile: config.descriptorFile ?? false,
   }
 }
 
@@ -107,6 +185,10 @@ function resolveAssetsEndpoint(
   return false
 }
 
+function trimUrlTrailingSlash(url: string) {
+  return url.endsWith('/') ? url.slice(0, url.length - 1) : url
+}
+
 export function trimPathSlashes(input: string) {
   input = input.replaceAll('\\', '/')
 
@@ -62,6 +62,29 @@ export type ViteRustPluginInput = {
    * @default false.
    */
   assetsEndpoint?: string | false,
+  /**
+   * Path to a "hot" file written while the dev server is running, holding its
+   * real URL (Vite moves to another port when the configured one is taken).
+   * vite-rust reads it when `ViteConfig::set_hot_file_path` is set, and the file
+   * is removed once the dev server stops.
+   *
+   * If set to `false`, no hot file is written.
+   *
+   * @default false
+   */
+  hotFile?: string | false,
+  /**
+   * Path to a JSON descriptor written at build and dev time, holding the
+   * manifest path (relative to the descriptor), the build's base, the
+   * entrypoints and the dev server URL. vite-rust reads it with
+   * `ViteConfig::from_descriptor`, so that these values aren't duplicated on
+   * the Rust side.
+   *
+   * If set to `false`, no descriptor is written.
+   *
+   * @default false
+   */
+  descriptorFile?: string | false,
 }
 
 export type ResolvedPluginConfig = Required<Omit<
@@ -1,4 +1,4 @@
-import type { UserConfig } from "vite";
+import type { UserConfig, ViteDevServer } from "vite";
 
 import rustVitePlugin from "../src";
 
@@ -41,4 +41,24 @@ describe("Validate Rust Vite Plugin behaviour", () => {
 
     expect(config.build?.manifest).toBe(false);
   });
+
+  it("should relay vite-rust's backend events to the browsers", () => {
+    const listeners = new Map<string, (data: unknown) => void>();
+    const send = vi.fn();
+    const server = {
+      ws: {
+        on: (event: string, listener: (data: unknown) => void) => listeners.set(event, listener),
+        send,
+      },
+    } as unknown as ViteDevServer;
+
+    const plugin = rustVitePlugin("src/main.ts")[0];
+    (plugin.configureServer as (server: ViteDevServer) => void)(server);
+
+    listeners.get("vite-rust:full-reload")?.(null);
+    listeners.get("vite-rust:error")?.({ message: "Template not found" });
+
+    expect(send).toHaveBeenCalledWith("vite-rust:full-reload", null);
+    expect(send).toHaveBeenCalledWith("vite-rust:error", { message: "Template not found" });
+  });
 });
@@ -21,6 +21,8 @@ describe("Validate Plugin Config Resolver", () => {
       outDir: "dist",
       publicEndpoint: "/",
       refresh: { paths: ["src/www/main.ts"] },
+      hotFile: false,
+      descriptorFile: false,
     } satisfies ResolvedPluginConfig);
   });
 
@@ -32,6 +34,8 @@ describe("Validate Plugin Config Resolver", () => {
       outDir: "output",
       publicEndpoint: "/public/",
       refresh: ["src/www/*"],
+      hotFile: "public/hot",
+      descriptorFile: "vite-rust.json",
     };
 
     const resolvedWithDefaults =
@@ -44,6 +48,8 @@ describe("Validate Plugin Config Resolver", () => {
       outDir: "output",
       publicEndpoint: "/public/",
       refresh: { paths: ["src/www/*"] },
+      hotFile: "public/hot",
+      descriptorFile: "vite-rust.json",
     } satisfies ResolvedPluginConfig);
   });
 });